@@ -1,10 +1,89 @@
-use std::io::{BufReader, BufWriter, Read, Write, stdin, stdout};
+use std::{
+    cell::Cell,
+    io::{BufReader, BufWriter, Read, Write, stdin, stdout},
+};
+
+use anyhow::bail;
+use lib_cli::ParsingRouter;
+
+struct FlagLenient(bool);
+impl From<bool> for FlagLenient {
+    fn from(value: bool) -> Self {
+        FlagLenient(value)
+    }
+}
+impl lib_cli::utils::FlagBool for FlagLenient {
+    const NAME: &str = "--lenient";
+    const SHORT_NAME: Option<&str> = Some("-l");
+    const ALIASES: &[&str] = &["--json5", "--jsonc"];
+    const DESCRIPTION: &str =
+        "accept comments, trailing commas, single quoted strings, unquoted keys and JSON5 numbers";
+}
+
+struct FlagCompact(bool);
+impl From<bool> for FlagCompact {
+    fn from(value: bool) -> Self {
+        FlagCompact(value)
+    }
+}
+impl lib_cli::utils::FlagBool for FlagCompact {
+    const NAME: &str = "--compact";
+    const SHORT_NAME: Option<&str> = Some("-c");
+    const DESCRIPTION: &str = "print each document on a single line, like NDJSON";
+}
 
 fn main() -> anyhow::Result<()> {
-    let mut stdin = BufReader::new(stdin()).bytes().peekable();
-    let mut stdout = BufWriter::new(stdout());
+    let cx = lib_cli::ParsingContext::from_args(lib_cli::Documentation {
+        names: lib_cli::Names::only_main("json_formatter"),
+        description: "formats json documents from stdin, several documents(like NDJSON) are formatted one by one",
+    });
+    let mut result = Ok(());
+    cx.wrapper(|lib_cli::utils::AppPath(_path), lib_cli::TailArgs(args)| {
+        args.current_command(
+            |FlagCompact(compact), FlagLenient(lenient), lib_cli::EmptyTail| {
+                result = format(stdin(), stdout(), compact, lenient);
+            },
+        );
+    });
+    result
+}
+
+fn format(
+    mut input: impl Read,
+    output: impl Write,
+    compact: bool,
+    lenient: bool,
+) -> anyhow::Result<()> {
+    if !lenient {
+        return format_json(input, output, compact);
+    }
+    // converted like in json_viewer, so both report the same errors at the same positions
+    let mut content = Vec::new();
+    input.read_to_end(&mut content)?;
+    let content = json_viewer::normalize_lenient(&content)?;
+    format_json(content.as_slice(), output, compact)
+}
 
-    let mut ctx = FormatterContext::default();
+/// Formats strict json, documents are read one by one
+fn format_json(input: impl Read, output: impl Write, compact: bool) -> anyhow::Result<()> {
+    // line and column of last read byte, for errors
+    let position = Cell::new((1, 0));
+    let mut stdin = BufReader::new(input)
+        .bytes()
+        .inspect(|byte| {
+            let (line, column) = position.get();
+            position.set(match byte {
+                Ok(b'\n') => (line + 1, 0),
+                _ => (line, column + 1),
+            });
+        })
+        .peekable();
+    let mut stdout = BufWriter::new(output);
+
+    let mut ctx = FormatterContext {
+        compact,
+        ..Default::default()
+    };
     while let Some(ch) = stdin.next() {
         let ch = ch?;
         match ch {
@@ -27,7 +106,10 @@ fn main() -> anyhow::Result<()> {
                 ctx.state = FormatterState::InStructWithoutName;
             }
             b']' => {
-                assert_eq!(ctx.parents.pop(), Some(ParentTy::Array));
+                if ctx.parents.pop() != Some(ParentTy::Array) {
+                    let (line, column) = position.get();
+                    bail!("unexpected ']' at line {line}, column {column}");
+                }
                 ctx.indentation -= 1;
                 indent_if_needed(&ctx, &mut stdout)?;
                 write!(stdout, "]")?;
@@ -44,7 +126,10 @@ fn main() -> anyhow::Result<()> {
                 });
             }
             b'}' => {
-                assert_eq!(ctx.parents.pop(), Some(ParentTy::Object));
+                if ctx.parents.pop() != Some(ParentTy::Object) {
+                    let (line, column) = position.get();
+                    bail!("unexpected '}}' at line {line}, column {column}");
+                }
                 ctx.indentation -= 1;
                 indent_if_needed(&ctx, &mut stdout)?;
                 write!(stdout, "}}")?;
@@ -66,44 +151,33 @@ fn main() -> anyhow::Result<()> {
                 write!(stdout, "\"")?;
                 let found = consume_string_to_end(&mut stdin, &mut stdout)?;
                 assert!(found);
-
-                if let FormatterState::InStructWithoutName = ctx.state {
-                    ctx.state = FormatterState::InStructWithName;
-
-                    assert_eq!(stdin.next().transpose()?, Some(b':'));
-                    write!(stdout, ": ")?;
-                } else {
-                    if let FormatterState::InStructWithName = ctx.state {
-                        ctx.state = FormatterState::InStructWithoutName
-                    }
-
-                    ctx.has_prev = true;
-                    let _ = stdin.next_if(|x| match x {
-                        Ok(ch) => *ch == b',',
-                        Err(_) => false,
-                    });
-                }
+                finish_string(&mut ctx, &mut stdin, &mut stdout)?;
             }
+            // commas are written before next value
+            b',' => {}
+            ch if ch.is_ascii_whitespace() => {}
             digit @ (b'-' | b'0'..=b'9') => {
                 add_comma_to_previous_object(&ctx, &mut stdout)?;
                 indent_if_needed(&ctx, &mut stdout)?;
                 write!(stdout, "{}", digit as char)?;
-                while let Some(ch) = peek(&mut stdin)
-                    && ch.is_ascii_digit()
-                {
-                    let ch = stdin.next().unwrap()?;
-                    write!(stdout, "{}", ch as char)?;
-                }
+                copy_digits(&mut stdin, &mut stdout)?;
                 if let Some(ch) = peek(&mut stdin)
                     && ch == b'.'
                 {
                     let ch = stdin.next().unwrap()?;
                     write!(stdout, "{}", ch as char)?;
-                    while let Some(ch) = peek(&mut stdin)
-                        && ch.is_ascii_digit()
-                    {
-                        let ch = stdin.next().unwrap()?;
-                        write!(stdout, "{}", ch as char)?;
+                    copy_digits(&mut stdin, &mut stdout)?;
+                }
+                if let Some(ch @ (b'e' | b'E')) = peek(&mut stdin) {
+                    stdin.next().unwrap()?;
+                    write!(stdout, "{}", ch as char)?;
+                    if let Some(sign @ (b'+' | b'-')) = peek(&mut stdin) {
+                        stdin.next().unwrap()?;
+                        write!(stdout, "{}", sign as char)?;
+                    }
+                    if copy_digits(&mut stdin, &mut stdout)? == 0 {
+                        let (line, column) = position.get();
+                        bail!("expected digits of exponent at line {line}, column {column}");
                     }
                 }
 
@@ -171,8 +245,12 @@ fn main() -> anyhow::Result<()> {
                     Err(_) => false,
                 });
             }
-            c => {
-                todo!("unknown character {}", c as char)
+            ch => {
+                let (line, column) = position.get();
+                bail!(
+                    "unexpected {:?} at line {line}, column {column}",
+                    ch as char
+                );
             }
         }
         while let Some(ch) = peek(&mut stdin)
@@ -188,8 +266,58 @@ fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Writes colon after key or finishes value
+fn finish_string(
+    ctx: &mut FormatterContext,
+    input: &mut std::iter::Peekable<impl Iterator<Item = Result<u8, std::io::Error>>>,
+    mut output: impl Write,
+) -> anyhow::Result<()> {
+    if let FormatterState::InStructWithoutName = ctx.state {
+        ctx.state = FormatterState::InStructWithName;
+
+        while let Some(ch) = peek(input)
+            && ch.is_ascii_whitespace()
+        {
+            input.next().transpose()?;
+        }
+        assert_eq!(input.next().transpose()?, Some(b':'));
+        write!(output, "{}", if ctx.compact { ":" } else { ": " })?;
+    } else {
+        if let FormatterState::InStructWithName = ctx.state {
+            ctx.state = FormatterState::InStructWithoutName
+        }
+
+        ctx.has_prev = true;
+        let _ = input.next_if(|x| match x {
+            Ok(ch) => *ch == b',',
+            Err(_) => false,
+        });
+    }
+    Ok(())
+}
+
+/// Copies digits of number
+/// # Returns number of copied digits
+fn copy_digits(
+    input: &mut std::iter::Peekable<impl Iterator<Item = Result<u8, std::io::Error>>>,
+    mut output: impl Write,
+) -> anyhow::Result<usize> {
+    let mut count = 0;
+    while let Some(ch) = peek(input)
+        && ch.is_ascii_digit()
+    {
+        let ch = input.next().unwrap()?;
+        write!(output, "{}", ch as char)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
 fn indent_if_needed(ctx: &FormatterContext, mut output: impl Write) -> std::io::Result<()> {
-    if ctx.state != FormatterState::InStructWithName && ctx.state != FormatterState::TopLevel {
+    if ctx.state != FormatterState::InStructWithName
+        && ctx.state != FormatterState::TopLevel
+        && !ctx.compact
+    {
         writeln!(output)?;
         write!(output, "{}", "  ".repeat(ctx.indentation))?;
     }
@@ -199,7 +327,10 @@ fn add_comma_to_previous_object(
     ctx: &FormatterContext,
     mut output: impl Write,
 ) -> std::io::Result<()> {
-    if ctx.state != FormatterState::InStructWithName && ctx.has_prev {
+    if ctx.state == FormatterState::TopLevel && ctx.has_prev {
+        // next document of stream
+        writeln!(output)?;
+    } else if ctx.state != FormatterState::InStructWithName && ctx.has_prev {
         write!(output, ",")?;
     }
     Ok(())
@@ -235,6 +366,8 @@ struct FormatterContext {
     parents: Vec<ParentTy>,
     indentation: usize,
     has_prev: bool,
+    /// Document per line without indentation
+    compact: bool,
 }
 
 /// Gets escaped string without opening quote and returns bytes count to closing quote(including it)
@@ -259,3 +392,93 @@ fn consume_string_to_end(
     }
     Ok(false)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format_lenient(input: &str) -> String {
+        let mut output = Vec::new();
+        format(input.as_bytes(), &mut output, true, true).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn comments_are_skipped() {
+        assert_eq!(
+            format_lenient("// header\n{\"a\" /* before colon */ : /* value */ 1, // tail\n}"),
+            "{\"a\":1}\n"
+        );
+        assert_eq!(format_lenient("[1 /* a */, /* b */ 2]"), "[1,2]\n");
+    }
+
+    #[test]
+    fn single_quoted_strings_are_double_quoted() {
+        assert_eq!(
+            format_lenient(r#"{'key': 'it\'s "quoted"'}"#),
+            r#"{"key":"it's \"quoted\""}"#.to_owned() + "\n"
+        );
+        assert_eq!(format_lenient(r"['a\nb']"), "[\"a\\nb\"]\n");
+    }
+
+    #[test]
+    fn trailing_commas_are_ignored() {
+        assert_eq!(format_lenient("[1, 2,]"), "[1,2]\n");
+        assert_eq!(format_lenient("{\"a\": [true,],}"), "{\"a\":[true]}\n");
+    }
+
+    #[test]
+    fn unquoted_keys_are_quoted() {
+        assert_eq!(
+            format_lenient("{key: 1, $id_2: null}"),
+            "{\"key\":1,\"$id_2\":null}\n"
+        );
+    }
+
+    #[test]
+    fn exponents_are_copied() {
+        assert_eq!(
+            format_lenient("[1e5, -2.5E-3, 0.5e+2]"),
+            "[1e5,-2.5E-3,0.5e+2]\n"
+        );
+        let mut output = Vec::new();
+        format(&b"{\"a\": 1E5}"[..], &mut output, false, false).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "{\n  \"a\": 1E5\n}\n");
+    }
+
+    #[test]
+    fn json5_numbers_are_converted() {
+        assert_eq!(format_lenient("[0x1F, .5, +1]"), "[31,0.5,1]\n");
+    }
+
+    #[test]
+    fn unexpected_characters_are_rejected_with_position() {
+        for (input, lenient, error) in [
+            ("[1,\n @]", false, "unexpected '@' at line 2, column 2"),
+            (
+                "[1e]",
+                false,
+                "expected digits of exponent at line 1, column 4",
+            ),
+            ("[foo]", true, "unexpected foo at line 1, column 2"),
+        ] {
+            let err = format(input.as_bytes(), Vec::new(), true, lenient).unwrap_err();
+            assert_eq!(err.to_string(), error);
+        }
+    }
+
+    #[test]
+    fn infinity_and_nan_are_rejected_with_position() {
+        for (input, error) in [
+            ("[Infinity]", "Infinity at line 1, column 2"),
+            ("{\n  \"a\": NaN\n}", "NaN at line 2, column 8"),
+            ("[1,\n -Infinity]", "-Infinity at line 2, column 2"),
+        ] {
+            let err = format(input.as_bytes(), Vec::new(), true, true).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("{error} can't be represented in JSON")
+            );
+        }
+    }
+}
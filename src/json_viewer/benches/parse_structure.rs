@@ -0,0 +1,70 @@
+//! Measures the same "parsing structure" step, that json_viewer prints on startup,
+//! and indexing of whole document.
+//!
+//! Uses generated document by default, set `JSON_VIEWER_BENCH_FILE` to measure real file:
+//! `JSON_VIEWER_BENCH_FILE=business-licences.json cargo bench -p json_viewer`
+
+use std::{hint::black_box, time::Instant};
+
+use json_viewer::*;
+
+const ITERATIONS: u32 = 10;
+
+fn main() -> anyhow::Result<()> {
+    let content: &'static [u8] = match std::env::var_os("JSON_VIEWER_BENCH_FILE") {
+        Some(path) => read_input(std::path::Path::new(&path))?,
+        None => generate_document(200_000).into_bytes().leak(),
+    };
+
+    let structure = parse_json_structure(content)?;
+    let memory_usage = structure.memory_usage();
+    let nodes = structure.len();
+    println!(
+        "document: {} MB, {nodes} nodes, {} MB of metadata({} bytes per node)",
+        content.len() / 1_000_000,
+        memory_usage / 1_000_000,
+        memory_usage / nodes.max(1),
+    );
+
+    measure("parsing structure", || {
+        parse_json_structure_lazy(black_box(content)).unwrap()
+    });
+    measure("parsing whole structure", || {
+        parse_json_structure(black_box(content)).unwrap()
+    });
+    Ok(())
+}
+
+fn measure(name: &str, mut f: impl FnMut() -> JsonMetadata) {
+    let mut timings = (0..ITERATIONS)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(f());
+            start.elapsed()
+        })
+        .collect::<Vec<_>>();
+    timings.sort();
+    let total = timings.iter().sum::<std::time::Duration>();
+    println!(
+        "{name} takes {}ms (min {}ms, median {}ms, max {}ms, {ITERATIONS} iterations)",
+        (total / ITERATIONS).as_millis(),
+        timings[0].as_millis(),
+        timings[timings.len() / 2].as_millis(),
+        timings[timings.len() - 1].as_millis(),
+    );
+}
+
+fn generate_document(records: usize) -> String {
+    let mut output = String::from("[");
+    for i in 0..records {
+        if i != 0 {
+            output.push(',');
+        }
+        output.push_str(&format!(
+            r#"
+  {{"id": {i}, "name": "record number {i}", "tags": ["a", "b", "c"], "nested": {{"value": -{i}.5, "flag": true, "none": null, "empty": {{}}}}}}"#
+        ));
+    }
+    output.push_str("\n]\n");
+    output
+}
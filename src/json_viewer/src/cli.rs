@@ -0,0 +1,212 @@
+use std::path::PathBuf;
+
+use anyhow::anyhow;
+
+/// Positional arguments shouldn't take flags, which come after other flags
+fn is_flag(cx: &lib_cli::ParsingContext) -> bool {
+    cx.args
+        .get(cx.cursor)
+        .is_some_and(|arg| arg.len() > 1 && arg.as_encoded_bytes().starts_with(b"-"))
+}
+
+/// `None` means stdin
+pub struct InputFile(pub Option<PathBuf>);
+impl lib_cli::Opt for InputFile {
+    fn try_parse_self(
+        this: &mut Option<Self>,
+        cx: &mut lib_cli::ParsingContext,
+    ) -> anyhow::Result<bool> {
+        if this.is_some() || is_flag(cx) {
+            return Ok(false);
+        }
+        if let Some(path) = cx.read_path() {
+            *this = Some(InputFile((path.as_os_str() != "-").then_some(path)));
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn finalize(this: Option<Self>) -> anyhow::Result<Self> {
+        Ok(this.unwrap_or(InputFile(None)))
+    }
+
+    fn add_documentation(store: &mut lib_cli::DocumentationStore) {
+        store.add(
+            "argument",
+            lib_cli::Documentation {
+                names: lib_cli::Names::only_main("?file"),
+                description: "json file to view, reads stdin if omitted or '-'",
+            },
+        );
+    }
+}
+
+/// Document to compare input with
+pub struct OtherFile(pub Option<PathBuf>);
+impl lib_cli::Opt for OtherFile {
+    fn try_parse_self(
+        this: &mut Option<Self>,
+        cx: &mut lib_cli::ParsingContext,
+    ) -> anyhow::Result<bool> {
+        if this.is_some() || is_flag(cx) {
+            return Ok(false);
+        }
+        if let Some(path) = cx.read_path() {
+            *this = Some(OtherFile(Some(path)));
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    fn finalize(this: Option<Self>) -> anyhow::Result<Self> {
+        Ok(this.unwrap_or(OtherFile(None)))
+    }
+
+    fn add_documentation(store: &mut lib_cli::DocumentationStore) {
+        store.add(
+            "argument",
+            lib_cli::Documentation {
+                names: lib_cli::Names::only_main("?other_file"),
+                description: "shows structural diff of file and other_file, if given",
+            },
+        );
+    }
+}
+
+/// Where `y`/`Y` put yanked text, `None` means terminal clipboard
+pub struct YankFile(pub Option<PathBuf>);
+impl lib_cli::Opt for YankFile {
+    fn try_parse_self(
+        this: &mut Option<Self>,
+        cx: &mut lib_cli::ParsingContext,
+    ) -> anyhow::Result<bool> {
+        if this.is_some()
+            || cx
+                .args
+                .get(cx.cursor)
+                .is_none_or(|arg| arg != "--yank-file")
+        {
+            return Ok(false);
+        }
+        cx.cursor += 1;
+        let path = cx
+            .read_path()
+            .ok_or(anyhow!("expected path after '--yank-file'"))?;
+        *this = Some(YankFile(Some(path)));
+        Ok(true)
+    }
+
+    fn finalize(this: Option<Self>) -> anyhow::Result<Self> {
+        Ok(this.unwrap_or(YankFile(None)))
+    }
+
+    fn add_documentation(store: &mut lib_cli::DocumentationStore) {
+        store.add(
+            "flag",
+            lib_cli::Documentation {
+                names: lib_cli::Names::only_main("--yank-file <path>"),
+                description: "write yanked value or path to file instead of clipboard",
+            },
+        );
+    }
+}
+
+/// Where expanded nodes, selection and marks are kept between launches
+pub struct SessionFile(pub Option<PathBuf>);
+impl lib_cli::Opt for SessionFile {
+    fn try_parse_self(
+        this: &mut Option<Self>,
+        cx: &mut lib_cli::ParsingContext,
+    ) -> anyhow::Result<bool> {
+        if this.is_some() || cx.args.get(cx.cursor).is_none_or(|arg| arg != "--session") {
+            return Ok(false);
+        }
+        cx.cursor += 1;
+        let path = cx
+            .read_path()
+            .ok_or(anyhow!("expected path after '--session'"))?;
+        *this = Some(SessionFile(Some(path)));
+        Ok(true)
+    }
+
+    fn finalize(this: Option<Self>) -> anyhow::Result<Self> {
+        Ok(this.unwrap_or(SessionFile(None)))
+    }
+
+    fn add_documentation(store: &mut lib_cli::DocumentationStore) {
+        store.add(
+            "flag",
+            lib_cli::Documentation {
+                names: lib_cli::Names::only_main("--session <path>"),
+                description: "restore and save expanded nodes, selection and marks of file",
+            },
+        );
+    }
+}
+
+/// `None` means default theme
+pub struct ThemeOpt(pub Option<json_viewer::ThemeName>);
+impl lib_cli::Opt for ThemeOpt {
+    fn try_parse_self(
+        this: &mut Option<Self>,
+        cx: &mut lib_cli::ParsingContext,
+    ) -> anyhow::Result<bool> {
+        if this.is_some() || cx.args.get(cx.cursor).is_none_or(|arg| arg != "--theme") {
+            return Ok(false);
+        }
+        cx.cursor += 1;
+        let name = cx
+            .args
+            .get(cx.cursor)
+            .ok_or(anyhow!("expected theme after '--theme'"))?
+            .to_str()
+            .ok_or(anyhow!("theme name should be utf-8"))?
+            .parse()?;
+        cx.cursor += 1;
+        *this = Some(ThemeOpt(Some(name)));
+        Ok(true)
+    }
+
+    fn finalize(this: Option<Self>) -> anyhow::Result<Self> {
+        Ok(this.unwrap_or(ThemeOpt(None)))
+    }
+
+    fn add_documentation(store: &mut lib_cli::DocumentationStore) {
+        store.add(
+            "flag",
+            lib_cli::Documentation {
+                names: lib_cli::Names::only_main("--theme <dark|light|monochrome>"),
+                description: "colour theme, monochrome by default if NO_COLOR is set",
+            },
+        );
+    }
+}
+
+pub struct PrintOnExit(pub bool);
+impl From<bool> for PrintOnExit {
+    fn from(value: bool) -> Self {
+        PrintOnExit(value)
+    }
+}
+impl lib_cli::utils::FlagBool for PrintOnExit {
+    const NAME: &str = "--print-on-exit";
+    const SHORT_NAME: Option<&str> = Some("-p");
+    const DESCRIPTION: &str =
+        "print selected value formatted to stdout on 'q', 'Q' quits without printing";
+}
+
+pub struct Lenient(pub bool);
+impl From<bool> for Lenient {
+    fn from(value: bool) -> Self {
+        Lenient(value)
+    }
+}
+impl lib_cli::utils::FlagBool for Lenient {
+    const NAME: &str = "--lenient";
+    const SHORT_NAME: Option<&str> = Some("-l");
+    const ALIASES: &[&str] = &["--json5", "--jsonc"];
+    const DESCRIPTION: &str =
+        "accept comments, trailing commas, single quoted strings and unquoted keys";
+}
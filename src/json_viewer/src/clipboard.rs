@@ -0,0 +1,27 @@
+/// Escape sequence, that asks terminal to put `text` into system clipboard.
+/// Works over ssh, but some terminals have to allow it in settings.
+pub fn osc52_copy(text: &[u8]) -> String {
+    format!("\x1b]52;c;{}\x07", base64_encode(text))
+}
+
+fn base64_encode(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut output = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let triple = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for position in 0..4 {
+            if position <= chunk.len() {
+                let sextet = (triple >> (18 - position * 6)) & 0x3f;
+                output.push(ALPHABET[sextet as usize] as char);
+            } else {
+                output.push('=');
+            }
+        }
+    }
+    output
+}
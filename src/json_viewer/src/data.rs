@@ -0,0 +1,91 @@
+use crate::*;
+
+/// Line of data pane, which shows subtree of selection as formatted json.
+/// Containers are shown as opening and closing lines if open, or as a single line if folded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataLine {
+    Open(JsonMetadataIndex),
+    /// Closing bracket of open container
+    Close(JsonMetadataIndex),
+}
+impl DataLine {
+    pub fn index(self) -> JsonMetadataIndex {
+        match self {
+            DataLine::Open(index) | DataLine::Close(index) => index,
+        }
+    }
+    /// Position in document, lines are in document order
+    pub fn position(self, structure: &JsonMetadata) -> usize {
+        match self {
+            DataLine::Open(index) => structure.source_start(index),
+            DataLine::Close(index) => {
+                let object = structure.get(index);
+                object.source_start + object.source_len - 1
+            }
+        }
+    }
+}
+
+impl JsonMetadata {
+    /// Folding in data pane mirrors `expanded`, except `root`, which is always open.
+    /// `root` should be indexed.
+    pub fn is_open_in(&self, root: JsonMetadataIndex, index: JsonMetadataIndex) -> bool {
+        matches!(self.ty(index), ObjectType::Array | ObjectType::Structure)
+            && (index == root || self.is_expanded(index))
+            && self.first_child(index).is_some()
+    }
+    pub fn next_data_line(&self, root: JsonMetadataIndex, line: DataLine) -> Option<DataLine> {
+        match line {
+            DataLine::Open(index) if self.is_open_in(root, index) => {
+                Some(DataLine::Open(self.first_child(index).unwrap()))
+            }
+            _ if line.index() == root => None,
+            _ => Some(match self.next(line.index()) {
+                Some(next) => DataLine::Open(next),
+                None => DataLine::Close(self.parent(line.index()).unwrap()),
+            }),
+        }
+    }
+    pub fn prev_data_line(&self, root: JsonMetadataIndex, line: DataLine) -> Option<DataLine> {
+        match line {
+            DataLine::Open(index) if index == root => None,
+            DataLine::Open(index) => Some(match self.prev(index) {
+                Some(prev) => self.last_data_line(root, prev),
+                None => DataLine::Open(self.parent(index).unwrap()),
+            }),
+            DataLine::Close(index) => {
+                let last_child = self.children(index).last().unwrap();
+                Some(self.last_data_line(root, last_child))
+            }
+        }
+    }
+    /// Last line of `index` subtree
+    pub fn last_data_line(&self, root: JsonMetadataIndex, index: JsonMetadataIndex) -> DataLine {
+        if self.is_open_in(root, index) {
+            DataLine::Close(index)
+        } else {
+            DataLine::Open(index)
+        }
+    }
+    /// Returns `line` or line of its folded ancestor, that hides it
+    pub fn nearest_data_line(&self, root: JsonMetadataIndex, line: DataLine) -> DataLine {
+        let mut visible = line;
+        let mut current = line.index();
+        if let DataLine::Close(index) = line
+            && !self.is_open_in(root, index)
+        {
+            visible = DataLine::Open(index);
+        }
+        while current != root {
+            let Some(parent) = self.parent(current) else {
+                // not in the subtree of `root`
+                return DataLine::Open(root);
+            };
+            if !self.is_open_in(root, parent) {
+                visible = DataLine::Open(parent);
+            }
+            current = parent;
+        }
+        visible
+    }
+}
@@ -0,0 +1,339 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffStatus {
+    Same,
+    /// Different scalar values or types
+    Changed,
+    /// Only in the right document
+    Added,
+    /// Only in the left document
+    Removed,
+    /// Container with differences inside
+    Modified,
+}
+impl DiffStatus {
+    /// Is this node a difference itself, not just a container of differences
+    pub fn is_difference(self) -> bool {
+        matches!(
+            self,
+            DiffStatus::Changed | DiffStatus::Added | DiffStatus::Removed
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DiffIndex(pub u32);
+impl DiffIndex {
+    pub const ROOT: Self = DiffIndex(0);
+    pub fn new(index: usize) -> Self {
+        DiffIndex(u32::try_from(index).unwrap())
+    }
+    pub fn get(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Pair of aligned nodes from two documents
+#[derive(Debug, Clone, Copy)]
+pub struct DiffNode {
+    pub left: Option<JsonMetadataIndex>,
+    pub right: Option<JsonMetadataIndex>,
+    pub status: DiffStatus,
+    pub parent: Option<DiffIndex>,
+    pub prev: Option<DiffIndex>,
+    pub next: Option<DiffIndex>,
+    pub first_child: Option<DiffIndex>,
+    pub expanded: bool,
+}
+
+/// Both documents merged into one tree, object members are aligned by key and array elements by index.
+/// Nodes are stored in pre-order, so index order is display order.
+pub struct DiffTree {
+    nodes: Vec<DiffNode>,
+}
+
+/// Both structures should be fully indexed
+pub fn diff_structures(
+    left_content: &Document,
+    left: &JsonMetadata,
+    right_content: &Document,
+    right: &JsonMetadata,
+) -> DiffTree {
+    let mut builder = DiffBuilder {
+        left_content,
+        left,
+        right_content,
+        right,
+        nodes: Vec::new(),
+    };
+    builder.add_tree(
+        (!left.is_empty()).then_some(JsonMetadataIndex::ROOT),
+        (!right.is_empty()).then_some(JsonMetadataIndex::ROOT),
+    );
+    builder.nodes[DiffIndex::ROOT.get()].expanded = true;
+    DiffTree {
+        nodes: builder.nodes,
+    }
+}
+
+struct DiffBuilder<'a> {
+    left_content: &'a Document,
+    left: &'a JsonMetadata,
+    right_content: &'a Document,
+    right: &'a JsonMetadata,
+    nodes: Vec<DiffNode>,
+}
+type Pair = (Option<JsonMetadataIndex>, Option<JsonMetadataIndex>);
+
+/// Node, which children are added by `add_tree`
+struct Level {
+    index: DiffIndex,
+    pairs: std::vec::IntoIter<Pair>,
+    prev_child: Option<DiffIndex>,
+    children_differ: bool,
+}
+
+impl DiffBuilder<'_> {
+    /// Adds nodes in pre-order, status of node is known after its children are added
+    fn add_tree(&mut self, left: Option<JsonMetadataIndex>, right: Option<JsonMetadataIndex>) {
+        // innermost last, so deep documents don't overflow call stack
+        let mut levels = vec![self.add(None, None, left, right)];
+        while let Some(level) = levels.last_mut() {
+            if let Some((left, right)) = level.pairs.next() {
+                let parent = level.index;
+                let prev = level.prev_child;
+                let child = self.add(Some(parent), prev, left, right);
+                levels.last_mut().unwrap().prev_child = Some(child.index);
+                levels.push(child);
+                continue;
+            }
+            let level = levels.pop().unwrap();
+            let status = self.finish(&level);
+            if let Some(parent) = levels.last_mut() {
+                parent.children_differ |= status != DiffStatus::Same;
+            }
+        }
+    }
+
+    fn add(
+        &mut self,
+        parent: Option<DiffIndex>,
+        prev: Option<DiffIndex>,
+        left: Option<JsonMetadataIndex>,
+        right: Option<JsonMetadataIndex>,
+    ) -> Level {
+        let index = DiffIndex::new(self.nodes.len());
+        self.nodes.push(DiffNode {
+            left,
+            right,
+            status: DiffStatus::Same,
+            parent,
+            prev,
+            next: None,
+            first_child: None,
+            expanded: false,
+        });
+        match (prev, parent) {
+            (Some(prev), _) => self.nodes[prev.get()].next = Some(index),
+            (None, Some(parent)) => self.nodes[parent.get()].first_child = Some(index),
+            (None, None) => {}
+        }
+        Level {
+            index,
+            pairs: self.child_pairs(left, right).into_iter(),
+            prev_child: None,
+            children_differ: false,
+        }
+    }
+
+    /// Sets status of node, that has all children added
+    fn finish(&mut self, level: &Level) -> DiffStatus {
+        let DiffNode { left, right, .. } = self.nodes[level.index.get()];
+        let status = match (left, right) {
+            (None, _) => DiffStatus::Added,
+            (_, None) => DiffStatus::Removed,
+            (Some(left), Some(right)) => {
+                let left_object = self.left.get(left);
+                let right_object = self.right.get(right);
+                let same_scalar = match left_object.ty {
+                    ObjectType::Array | ObjectType::Structure => false,
+                    ObjectType::EmptyArray | ObjectType::EmptyStructure => true,
+                    _ => {
+                        left_object.source(self.left_content)
+                            == right_object.source(self.right_content)
+                    }
+                };
+                if left_object.ty != right_object.ty {
+                    DiffStatus::Changed
+                } else if level.children_differ {
+                    DiffStatus::Modified
+                } else if same_scalar || level.prev_child.is_some() {
+                    DiffStatus::Same
+                } else {
+                    DiffStatus::Changed
+                }
+            }
+        };
+        let node = &mut self.nodes[level.index.get()];
+        node.status = status;
+        // unchanged subtrees are collapsed
+        node.expanded = matches!(status, DiffStatus::Modified | DiffStatus::Changed);
+        status
+    }
+
+    /// Aligns children of containers with the same type, otherwise lists them one after another.
+    /// Duplicate keys are paired in order of occurrence, extra ones are removed or added.
+    fn child_pairs(
+        &self,
+        left: Option<JsonMetadataIndex>,
+        right: Option<JsonMetadataIndex>,
+    ) -> Vec<Pair> {
+        let left_children: Vec<_> = left
+            .map(|left| self.left.children(left).collect())
+            .unwrap_or_default();
+        let right_children: Vec<_> = right
+            .map(|right| self.right.children(right).collect())
+            .unwrap_or_default();
+        let same_type = left
+            .zip(right)
+            .map(|(left, right)| (self.left.ty(left), self.right.ty(right)));
+        match same_type {
+            Some((ObjectType::Structure, ObjectType::Structure)) => {
+                let right_name = |child| self.right.get(child).name(self.right_content).unwrap();
+                // last occurrence first, so occurrences are popped in order
+                let mut right_by_name: HashMap<&[u8], Vec<JsonMetadataIndex>> = HashMap::new();
+                for &child in right_children.iter().rev() {
+                    right_by_name
+                        .entry(right_name(child))
+                        .or_default()
+                        .push(child);
+                }
+                let mut paired = HashSet::new();
+                let mut pairs: Vec<_> = left_children
+                    .iter()
+                    .map(|&child| {
+                        let name = self.left.get(child).name(self.left_content).unwrap();
+                        let right = right_by_name.get_mut(name).and_then(Vec::pop);
+                        paired.extend(right);
+                        (Some(child), right)
+                    })
+                    .collect();
+                pairs.extend(
+                    right_children
+                        .iter()
+                        .filter(|child| !paired.contains(*child))
+                        .map(|&child| (None, Some(child))),
+                );
+                pairs
+            }
+            Some((ObjectType::Array, ObjectType::Array)) => {
+                let len = left_children.len().max(right_children.len());
+                (0..len)
+                    .map(|position| {
+                        (
+                            left_children.get(position).copied(),
+                            right_children.get(position).copied(),
+                        )
+                    })
+                    .collect()
+            }
+            _ => left_children
+                .iter()
+                .map(|&child| (Some(child), None))
+                .chain(right_children.iter().map(|&child| (None, Some(child))))
+                .collect(),
+        }
+    }
+}
+
+impl DiffTree {
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+    pub fn get(&self, index: DiffIndex) -> DiffNode {
+        self.nodes[index.get()]
+    }
+    pub fn toggle_expanded(&mut self, index: DiffIndex) {
+        let node = &mut self.nodes[index.get()];
+        node.expanded = !node.expanded;
+    }
+    pub fn set_expanded(&mut self, index: DiffIndex, expanded: bool) {
+        self.nodes[index.get()].expanded = expanded;
+    }
+    pub fn next_visible(&self, index: DiffIndex) -> Option<DiffIndex> {
+        let node = self.get(index);
+        if node.expanded && node.first_child.is_some() {
+            return node.first_child;
+        }
+        let mut current = node;
+        loop {
+            if current.next.is_some() {
+                return current.next;
+            }
+            current = self.get(current.parent?);
+        }
+    }
+    pub fn prev_visible(&self, index: DiffIndex) -> Option<DiffIndex> {
+        let node = self.get(index);
+        match node.prev {
+            Some(prev) => Some(self.last_in_childrens(prev)),
+            None => node.parent,
+        }
+    }
+    fn last_in_childrens(&self, mut index: DiffIndex) -> DiffIndex {
+        loop {
+            let node = self.get(index);
+            let Some(mut last) = node.first_child.filter(|_| node.expanded) else {
+                return index;
+            };
+            while let Some(next) = self.get(last).next {
+                last = next;
+            }
+            index = last;
+        }
+    }
+    pub fn last_visible(&self) -> DiffIndex {
+        self.last_in_childrens(DiffIndex::ROOT)
+    }
+    /// Returns `index` or its collapsed ancestor, that hides it
+    pub fn nearest_visible(&self, index: DiffIndex) -> DiffIndex {
+        let mut visible = index;
+        let mut current = index;
+        while let Some(parent) = self.get(current).parent {
+            if !self.get(parent).expanded {
+                visible = parent;
+            }
+            current = parent;
+        }
+        visible
+    }
+    pub fn expand_ancestors(&mut self, index: DiffIndex) {
+        let mut current = index;
+        while let Some(parent) = self.get(current).parent {
+            self.nodes[parent.get()].expanded = true;
+            current = parent;
+        }
+    }
+    pub fn depth(&self, index: DiffIndex) -> usize {
+        std::iter::successors(self.get(index).parent, |&parent| self.get(parent).parent).count()
+    }
+    /// All differences in display order, without nodes inside of added or removed subtrees
+    pub fn differences(&self) -> Vec<DiffIndex> {
+        (0..self.nodes.len())
+            .map(DiffIndex::new)
+            .filter(|&index| {
+                let node = self.get(index);
+                node.status.is_difference()
+                    && node
+                        .parent
+                        .is_none_or(|parent| !self.get(parent).status.is_difference())
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,287 @@
+use crate::{outline::*, *};
+
+const SEPARATOR: &str = " | ";
+
+/// Document on one side of diff
+pub struct Side {
+    pub content: Document,
+    pub structure: JsonMetadata,
+}
+
+/// Side by side diff without terminal, differences are jumped to with `n`/`N`
+pub struct DiffViewer {
+    left: Side,
+    right: Side,
+    diff: DiffTree,
+    /// In display order
+    differences: Vec<DiffIndex>,
+    theme: Theme,
+    width: u16,
+    height: u16,
+    scroll: DiffIndex,
+    selection: DiffIndex,
+}
+
+impl DiffViewer {
+    /// `width` and `height` are size of the whole frame, including status line
+    pub fn new(
+        left: Side,
+        right: Side,
+        diff: DiffTree,
+        width: u16,
+        height: u16,
+        theme: Theme,
+    ) -> Self {
+        DiffViewer {
+            left,
+            right,
+            differences: diff.differences(),
+            diff,
+            theme,
+            width,
+            height,
+            scroll: DiffIndex::ROOT,
+            selection: DiffIndex::ROOT,
+        }
+    }
+
+    pub fn selection(&self) -> DiffIndex {
+        self.selection
+    }
+
+    /// Width of left half, halves are separated with `SEPARATOR`
+    fn left_width(&self) -> u16 {
+        self.width.saturating_sub(SEPARATOR.len() as u16) / 2
+    }
+
+    fn outline_height(&self) -> u16 {
+        // last line is status line
+        self.height.saturating_sub(1)
+    }
+}
+
+impl Interactive for DiffViewer {
+    fn render(&mut self) -> Vec<String> {
+        let height = self.outline_height();
+        let node = self.diff.get(self.selection);
+        let path = match (node.left, node.right) {
+            (_, Some(right_ix)) => path_to(&self.right.content, &self.right.structure, right_ix),
+            (Some(left_ix), None) => path_to(&self.left.content, &self.left.structure, left_ix),
+            (None, None) => ".".to_owned(),
+        };
+        let counter = if self.differences.is_empty() {
+            "[no differences]".to_owned()
+        } else {
+            let current = match self.differences.binary_search(&self.selection) {
+                Ok(position) => (position + 1).to_string(),
+                Err(_) => "?".to_owned(),
+            };
+            format!("[difference {current}/{}]", self.differences.len())
+        };
+        let left_width = self.left_width() as usize;
+        let right_width = self.width.saturating_sub(SEPARATOR.len() as u16) as usize - left_width;
+        let mut lines = render_diff(
+            &self.left,
+            &self.right,
+            &self.diff,
+            self.scroll,
+            height as usize,
+            self.selection,
+            &self.theme,
+        )
+        .into_iter()
+        .map(|(left_line, right_line)| {
+            format!(
+                "{}{RESET}{SEPARATOR}{}{RESET}",
+                fit_to_width(&left_line, 0, left_width),
+                fit_to_width(&right_line, 0, right_width),
+            )
+        })
+        .collect::<Vec<_>>();
+        lines.resize(height as usize, String::new());
+        lines.push(fit_to_width(
+            &sanitize(&format!("{path}    {counter}")),
+            0,
+            self.width as usize,
+        ));
+        lines
+    }
+
+    fn handle_key(&mut self, key: Key) -> Option<Exit> {
+        let height = self.outline_height();
+        let left_width = self.left_width();
+        let diff = &mut self.diff;
+        let differences = &self.differences;
+        let mut jump_target = None;
+        match key {
+            Key::Char('q') => return Some(Exit::Quit),
+            Key::Char('Q') => return Some(Exit::QuitWithoutPrinting),
+            Key::Char('j') | Key::Down => {
+                if let Some(index) = diff.next_visible(self.selection) {
+                    self.selection = index;
+                }
+            }
+            Key::Char('k') | Key::Up => {
+                if let Some(index) = diff.prev_visible(self.selection) {
+                    self.selection = index;
+                }
+            }
+            Key::Char('h') | Key::Left => {
+                let node = diff.get(self.selection);
+                if node.expanded && node.first_child.is_some() {
+                    diff.set_expanded(self.selection, false);
+                } else if let Some(parent) = node.parent {
+                    self.selection = parent;
+                }
+            }
+            Key::Char('l') | Key::Right => {
+                let node = diff.get(self.selection);
+                if !node.expanded {
+                    diff.set_expanded(self.selection, true);
+                } else if let Some(first_child) = node.first_child {
+                    self.selection = first_child;
+                }
+            }
+            Key::Enter => diff.toggle_expanded(self.selection),
+            Key::PageDown | Key::Ctrl('f') => {
+                for _ in 0..height {
+                    let Some(index) = diff.next_visible(self.selection) else {
+                        break;
+                    };
+                    self.selection = index;
+                }
+            }
+            Key::PageUp | Key::Ctrl('b') => {
+                for _ in 0..height {
+                    let Some(index) = diff.prev_visible(self.selection) else {
+                        break;
+                    };
+                    self.selection = index;
+                }
+            }
+            // halves are indented the same, so click on either of them works like in overview
+            Key::Mouse(mouse) => {
+                let right_start = left_width + SEPARATOR.len() as u16;
+                let column = mouse
+                    .column
+                    .checked_sub(right_start)
+                    .unwrap_or(mouse.column);
+                let down = mouse.kind == MouseKind::WheelDown;
+                match mouse.kind {
+                    MouseKind::Press if mouse.row < height => {
+                        match click(diff, self.scroll, height, mouse.row, column) {
+                            Some(Click::Toggle(index)) => diff.toggle_expanded(index),
+                            Some(Click::Select(index)) => self.selection = index,
+                            None => {}
+                        }
+                    }
+                    MouseKind::WheelUp | MouseKind::WheelDown => {
+                        scroll_wheel(diff, &mut self.scroll, &mut self.selection, height, down);
+                    }
+                    MouseKind::Press | MouseKind::Drag | MouseKind::Release => {}
+                }
+            }
+            Key::Char('g') | Key::Home => self.selection = DiffIndex::ROOT,
+            Key::Char('G') | Key::End => self.selection = diff.last_visible(),
+            Key::Char('n') => {
+                let position = differences.partition_point(|&index| index <= self.selection);
+                jump_target = differences.get(position).or(differences.first()).copied();
+            }
+            Key::Char('N') => {
+                let position = differences.partition_point(|&index| index < self.selection);
+                jump_target = match position {
+                    0 => differences.last().copied(),
+                    _ => Some(differences[position - 1]),
+                };
+            }
+            _ => {}
+        }
+        if let Some(target) = jump_target {
+            diff.expand_ancestors(target);
+            self.selection = target;
+            self.scroll = center_on(diff, self.selection, height);
+        }
+        self.selection = diff.nearest_visible(self.selection);
+        self.scroll = diff.nearest_visible(self.scroll);
+        self.scroll = scroll_to_selection(diff, self.scroll, self.selection, height);
+        None
+    }
+
+    fn resize(&mut self, width: u16, height: u16) {
+        self.width = width;
+        self.height = height;
+    }
+}
+
+/// # Returns left and right half of each line
+fn render_diff(
+    left: &Side,
+    right: &Side,
+    diff: &DiffTree,
+    start: DiffIndex,
+    lines_needed: usize,
+    selection: DiffIndex,
+    theme: &Theme,
+) -> Vec<(String, String)> {
+    let mut lines = Vec::new();
+    let mut current = Some(start);
+    while let Some(index) = current
+        && lines.len() < lines_needed
+    {
+        let node = diff.get(index);
+        let style = match node.status {
+            DiffStatus::Same => "",
+            DiffStatus::Changed => &theme.changed,
+            DiffStatus::Added => &theme.added,
+            DiffStatus::Removed => &theme.removed,
+            DiffStatus::Modified => &theme.modified,
+        };
+        let selected = if index == selection {
+            theme.selection.as_str()
+        } else {
+            ""
+        };
+        let marker = match (node.first_child, node.expanded) {
+            (None, _) => ' ',
+            (Some(_), true) => '-',
+            (Some(_), false) => '+',
+        };
+        let depth = diff.depth(index);
+        let render_side = |side: &Side, side_index: Option<JsonMetadataIndex>| {
+            let Some(side_index) = side_index else {
+                return selected.to_owned();
+            };
+            let object = side.structure.get(side_index);
+            let name = match object.name_or_index {
+                NameOrIndex::Name { .. } => display_text(
+                    str::from_utf8(object.name(&side.content).unwrap()).unwrap(),
+                    false,
+                )
+                .into_owned(),
+                NameOrIndex::Index(position) => position.to_string(),
+                // shown like first element
+                NameOrIndex::Root => "0".to_owned(),
+            };
+            let value = match object.ty {
+                ObjectType::Array => format!("{ITALIC}arr"),
+                ObjectType::Structure => format!("{ITALIC}obj"),
+                ObjectType::EmptyArray => "[]".to_owned(),
+                ObjectType::EmptyStructure => "{}".to_owned(),
+                _ => display_text(
+                    str::from_utf8(&object.source(&side.content)).unwrap(),
+                    false,
+                )
+                .into_owned(),
+            };
+            let indentation = if depth == 0 {
+                String::new()
+            } else {
+                format!("{}{marker} ", "  ".repeat(depth - 1))
+            };
+            format!("{selected}{style}{indentation}{name} {value}")
+        };
+        lines.push((render_side(left, node.left), render_side(right, node.right)));
+        current = diff.next_visible(index);
+    }
+    lines
+}
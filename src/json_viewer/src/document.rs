@@ -0,0 +1,195 @@
+use std::{borrow::Cow, io::Write, ops::Range, rc::Rc};
+
+/// Part of document, that is in original input or in bytes inserted by edit
+#[derive(Clone)]
+pub(crate) struct Piece {
+    /// Position in document
+    start: usize,
+    /// `None` for original input
+    buffer: Option<Rc<[u8]>>,
+    /// Range in original input or in `buffer`
+    range: Range<usize>,
+}
+
+impl Piece {
+    pub(crate) fn len(&self) -> usize {
+        self.range.len()
+    }
+}
+
+/// Json decoded from string, that is read instead of the string at the same positions
+struct Overlay {
+    start: usize,
+    bytes: Vec<u8>,
+}
+
+/// Input document with edits applied as pieces of original input and of inserted bytes,
+/// so edits don't copy the document and replaced bytes are freed, when their edit is dropped.
+/// Edits replace whole values, so values and keys are never split between pieces.
+pub struct Document {
+    original: Cow<'static, [u8]>,
+    /// In document order, without gaps
+    pieces: Vec<Piece>,
+    /// Decoded strings, nested ones after strings they are decoded from.
+    /// They aren't part of document, so they aren't written.
+    overlays: Vec<Overlay>,
+}
+
+impl Document {
+    pub fn new(original: impl Into<Cow<'static, [u8]>>) -> Self {
+        let original = original.into();
+        let pieces = match original.len() {
+            0 => Vec::new(),
+            len => vec![Piece {
+                start: 0,
+                buffer: None,
+                range: 0..len,
+            }],
+        };
+        Document {
+            original,
+            pieces,
+            overlays: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.pieces
+            .last()
+            .map_or(0, |piece| piece.start + piece.len())
+    }
+    pub fn is_empty(&self) -> bool {
+        self.pieces.is_empty()
+    }
+
+    fn bytes<'a>(&'a self, piece: &'a Piece) -> &'a [u8] {
+        match &piece.buffer {
+            Some(buffer) => &buffer[piece.range.clone()],
+            None => &self.original[piece.range.clone()],
+        }
+    }
+
+    /// # Returns position of piece, that has `position`, and its bytes
+    pub fn chunk(&self, position: usize) -> (usize, &[u8]) {
+        let overlay = self.overlays.iter().rev().find(|overlay| {
+            overlay.start <= position && position < overlay.start + overlay.bytes.len()
+        });
+        if let Some(overlay) = overlay {
+            return (overlay.start, &overlay.bytes);
+        }
+        let index = self.pieces.partition_point(|piece| piece.start <= position) - 1;
+        let piece = &self.pieces[index];
+        (piece.start, self.bytes(piece))
+    }
+
+    /// Bytes of value or key, which are never split between pieces
+    pub fn token(&self, range: Range<usize>) -> &[u8] {
+        let (start, chunk) = self.chunk(range.start);
+        &chunk[range.start - start..range.end - start]
+    }
+
+    /// Bytes of `range`, copied only if it spans several pieces
+    pub fn get(&self, range: Range<usize>) -> Cow<'_, [u8]> {
+        if range.is_empty() {
+            return Cow::Borrowed(&[]);
+        }
+        let (start, chunk) = self.chunk(range.start);
+        if range.end - start <= chunk.len() {
+            return Cow::Borrowed(&chunk[range.start - start..range.end - start]);
+        }
+        let mut bytes = Vec::with_capacity(range.len());
+        let mut position = range.start;
+        while position < range.end {
+            let (start, chunk) = self.chunk(position);
+            let end = chunk.len().min(range.end - start);
+            bytes.extend_from_slice(&chunk[position - start..end]);
+            position = start + end;
+        }
+        Cow::Owned(bytes)
+    }
+
+    pub fn chunks(&self) -> impl Iterator<Item = &[u8]> {
+        self.pieces.iter().map(|piece| self.bytes(piece))
+    }
+    pub fn to_vec(&self) -> Vec<u8> {
+        self.chunks().collect::<Vec<_>>().concat()
+    }
+    pub fn write_to(&self, output: &mut impl Write) -> std::io::Result<()> {
+        for chunk in self.chunks() {
+            output.write_all(chunk)?;
+        }
+        Ok(())
+    }
+
+    /// Reads `bytes` at `start` instead of document, until overlay at `start` is removed.
+    /// `bytes` should fit inside of string, so positions after it don't change.
+    pub(crate) fn add_overlay(&mut self, start: usize, bytes: Vec<u8>) {
+        self.overlays.push(Overlay { start, bytes });
+    }
+    pub(crate) fn remove_overlay(&mut self, start: usize) {
+        self.overlays.retain(|overlay| overlay.start != start);
+    }
+
+    /// Replaces `range` with `inserted` bytes
+    /// # Returns replaced pieces, that are put back with `restore`
+    pub(crate) fn splice(&mut self, range: Range<usize>, inserted: Vec<u8>) -> Vec<Piece> {
+        let piece = (!inserted.is_empty()).then(|| Piece {
+            start: range.start,
+            range: 0..inserted.len(),
+            buffer: Some(inserted.into()),
+        });
+        self.replace(range, piece.into_iter().collect())
+    }
+
+    /// Puts back pieces, that were replaced by `len` bytes at `start`
+    pub(crate) fn restore(&mut self, start: usize, len: usize, removed: Vec<Piece>) {
+        self.replace(start..start + len, removed);
+    }
+
+    fn replace(&mut self, range: Range<usize>, pieces: Vec<Piece>) -> Vec<Piece> {
+        let first = self.split(range.start);
+        let last = self.split(range.end);
+        let removed: Vec<_> = self.pieces.drain(first..last).collect();
+        let inserted: usize = pieces.iter().map(Piece::len).sum();
+        let delta = inserted as isize - range.len() as isize;
+        for piece in &mut self.pieces[first..] {
+            piece.start = (piece.start as isize + delta) as usize;
+        }
+        let mut position = range.start;
+        let pieces = pieces.into_iter().map(|mut piece| {
+            piece.start = position;
+            position += piece.len();
+            piece
+        });
+        self.pieces.splice(first..first, pieces);
+        removed
+    }
+
+    /// Splits piece, that has `position` inside
+    /// # Returns index of piece, that starts at `position`
+    fn split(&mut self, position: usize) -> usize {
+        let index = self.pieces.partition_point(|piece| piece.start < position);
+        if let Some(piece) = index.checked_sub(1).map(|prev| &mut self.pieces[prev])
+            && position < piece.start + piece.len()
+        {
+            let offset = piece.range.start + position - piece.start;
+            let mut second = piece.clone();
+            second.start = position;
+            second.range.start = offset;
+            piece.range.end = offset;
+            self.pieces.insert(index, second);
+        }
+        index
+    }
+}
+
+impl From<&'static [u8]> for Document {
+    fn from(original: &'static [u8]) -> Self {
+        Document::new(original)
+    }
+}
+impl From<Vec<u8>> for Document {
+    fn from(original: Vec<u8>) -> Self {
+        Document::new(original)
+    }
+}
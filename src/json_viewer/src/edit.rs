@@ -0,0 +1,440 @@
+use std::{
+    borrow::Cow,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, bail};
+
+use crate::*;
+
+/// `removed` pieces at `start` replaced with `inserted_len` bytes
+struct Edit {
+    start: usize,
+    inserted_len: usize,
+    removed: Vec<Piece>,
+    change: Change,
+}
+
+/// How nodes were changed by edit, undone in place too
+enum Change {
+    /// Scalar had type `ty` before
+    Replace {
+        index: JsonMetadataIndex,
+        ty: ObjectType,
+    },
+    /// Node is unlinked, but keeps its links to be put back
+    Delete(JsonMetadataIndex),
+    /// Copy, that was inserted
+    Insert(JsonMetadataIndex),
+}
+
+/// Edits over original document, which could be undone in reverse order.
+/// Bytes outside of edited ranges are kept, so formatting of saved document doesn't change.
+/// Nodes are updated in place, so expansion, sorting and indexed nodes are kept.
+pub struct Patch {
+    edits: Vec<Edit>,
+    /// Number of edits at last save, `None` if saved state was undone and replaced
+    saved: Option<usize>,
+}
+
+impl Default for Patch {
+    fn default() -> Self {
+        Patch {
+            edits: Vec::new(),
+            saved: Some(0),
+        }
+    }
+}
+
+impl Patch {
+    pub fn len(&self) -> usize {
+        self.edits.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+    /// Differs from saved or original document
+    pub fn is_modified(&self) -> bool {
+        self.saved != Some(self.edits.len())
+    }
+    pub fn mark_saved(&mut self) {
+        self.saved = Some(self.edits.len());
+    }
+
+    fn push(&mut self, start: usize, inserted_len: usize, removed: Vec<Piece>, change: Change) {
+        if self.saved.is_some_and(|saved| saved > self.edits.len()) {
+            self.saved = None;
+        }
+        self.edits.push(Edit {
+            start,
+            inserted_len,
+            removed,
+            change,
+        });
+    }
+
+    /// # Returns false if there is nothing to undo
+    pub fn undo(&mut self, content: &mut Document, structure: &mut JsonMetadata) -> bool {
+        let Some(edit) = self.edits.pop() else {
+            return false;
+        };
+        let removed_len = edit.removed.iter().map(Piece::len).sum();
+        let inserted = edit.start..edit.start + edit.inserted_len;
+        content.restore(edit.start, edit.inserted_len, edit.removed);
+        match edit.change {
+            Change::Replace { index, ty } => {
+                structure.shift(inserted, removed_len);
+                structure.ty[index.get()] = ty;
+                structure.clear_sort_around(index);
+            }
+            Change::Delete(index) => {
+                structure.shift(inserted, removed_len);
+                structure.relink(index);
+            }
+            Change::Insert(index) => {
+                structure.unlink(index);
+                structure.shift(inserted, removed_len);
+            }
+        }
+        true
+    }
+
+    /// Replaces scalar with another scalar, `value` is json text like `"text"` or `12`
+    pub fn replace_value(
+        &mut self,
+        content: &mut Document,
+        structure: &mut JsonMetadata,
+        index: JsonMetadataIndex,
+        value: &str,
+    ) -> Result<()> {
+        let object = structure.get(index);
+        if !object.ty.is_scalar() {
+            bail!("only strings, numbers, bools and null could be edited");
+        }
+        let value = value.trim().as_bytes();
+        if !is_scalar(value) {
+            bail!(
+                "not a json string, number, bool or null: {}",
+                String::from_utf8_lossy(value)
+            );
+        }
+        let range = object.source_start..object.source_start + object.source_len;
+        let removed = content.splice(range.clone(), value.to_vec());
+        structure.shift(range.clone(), value.len());
+        structure.ty[index.get()] = scan_value(value, 0).unwrap().0;
+        structure.clear_sort_around(index);
+        let change = Change::Replace {
+            index,
+            ty: object.ty,
+        };
+        self.push(range.start, value.len(), removed, change);
+        Ok(())
+    }
+
+    pub fn toggle_bool(
+        &mut self,
+        content: &mut Document,
+        structure: &mut JsonMetadata,
+        index: JsonMetadataIndex,
+    ) -> Result<()> {
+        let value = match structure.get(index).source(content).as_ref() {
+            b"true" => "false",
+            b"false" => "true",
+            _ => bail!("not a bool"),
+        };
+        self.replace_value(content, structure, index, value)
+    }
+
+    /// Removes array element or object member with its key and one of the separating commas
+    pub fn delete(
+        &mut self,
+        content: &mut Document,
+        structure: &mut JsonMetadata,
+        index: JsonMetadataIndex,
+    ) -> Result<()> {
+        let Some(parent) = structure.parent(index) else {
+            bail!("top level value can't be deleted");
+        };
+        let item = item_range(structure, index);
+        let range = match (structure.prev(index), structure.next(index)) {
+            (_, Some(next)) => item.start..item_range(structure, next).start,
+            (Some(prev), None) => item_range(structure, prev).end..item.end,
+            // container becomes empty, whitespace inside is removed too
+            (None, None) => {
+                let parent = structure.get(parent);
+                parent.source_start + 1..parent.source_start + parent.source_len - 1
+            }
+        };
+        structure.unlink(index);
+        let removed = content.splice(range.clone(), Vec::new());
+        structure.shift(range.clone(), 0);
+        self.push(range.start, 0, removed, Change::Delete(index));
+        Ok(())
+    }
+
+    /// Inserts copy of array element after it, separated like the element from its neighbour
+    pub fn duplicate(
+        &mut self,
+        content: &mut Document,
+        structure: &mut JsonMetadata,
+        index: JsonMetadataIndex,
+    ) -> Result<()> {
+        let Some(parent) = structure.parent(index) else {
+            bail!("top level value can't be duplicated");
+        };
+        if structure.ty(parent) != ObjectType::Array {
+            bail!("only array elements could be duplicated, keys must be unique");
+        }
+        let item = item_range(structure, index);
+        let separator = match (structure.prev(index), structure.next(index)) {
+            (Some(prev), _) => content.get(item_range(structure, prev).end..item.start),
+            (None, Some(next)) => content.get(item.end..item_range(structure, next).start),
+            (None, None) => Cow::Borrowed(b", ".as_slice()),
+        };
+        let mut inserted = separator.into_owned();
+        let copy_start = item.end + inserted.len();
+        inserted.extend_from_slice(&content.get(item.clone()));
+        let inserted_len = inserted.len();
+        let removed = content.splice(item.end..item.end, inserted);
+        structure.shift(item.end..item.end, inserted_len);
+        let object = structure.get(index);
+        let NameOrIndex::Index(position) = object.name_or_index else {
+            unreachable!("array elements have indices");
+        };
+        // nested containers of copy are indexed on expand
+        let copy = structure.push(ObjectMeta {
+            name_or_index: NameOrIndex::Index(position + 1),
+            source_start: copy_start,
+            prev: Some(index),
+            next: object.next,
+            expanded: false,
+            ..object
+        });
+        structure.relink(copy);
+        self.push(item.end, inserted_len, removed, Change::Insert(copy));
+        Ok(())
+    }
+}
+
+impl JsonMetadata {
+    /// Moves nodes after `range` and resizes nodes around it like document,
+    /// where `range` was replaced with `inserted_len` bytes.
+    /// Unlinked nodes are left as is, they are put back by undo in the same document.
+    pub(crate) fn shift(&mut self, range: Range<usize>, inserted_len: usize) {
+        if self.is_empty() {
+            return;
+        }
+        let delta = inserted_len as isize - range.len() as isize;
+        let root = JsonMetadataIndex::ROOT;
+        let mut current = Some(root);
+        while let Some(index) = current {
+            let i = index.get();
+            let (start, end) = (
+                self.source_start[i],
+                self.source_start[i] + self.source_len[i],
+            );
+            let around = start <= range.start
+                && range.end <= end
+                && !(range.is_empty() && end == range.start);
+            if start >= range.end {
+                self.source_start[i] = (start as isize + delta) as usize;
+            } else if around {
+                self.source_len[i] = (self.source_len[i] as isize + delta) as usize;
+            }
+            // nodes before range have nothing to change inside
+            current = self.next_in_subtree(root, index, end <= range.start);
+        }
+    }
+
+    /// Clears sorting of ancestors, whose sort key could be changed value
+    fn clear_sort_around(&mut self, index: JsonMetadataIndex) {
+        let mut current = self.parent(index);
+        while let Some(ancestor) = current {
+            self.clear_sort(ancestor);
+            current = self.parent(ancestor);
+        }
+    }
+
+    /// Removes node from siblings, indices of following array elements are decreased.
+    /// Links of node itself are kept for `relink`.
+    fn unlink(&mut self, index: JsonMetadataIndex) {
+        let object = self.get(index);
+        let parent = object.parent.unwrap();
+        match object.prev {
+            Some(prev) => self.next[prev.get()] = object.next.into(),
+            None => self.first_child[parent.get()] = object.next.into(),
+        }
+        if let Some(next) = object.next {
+            self.prev[next.get()] = object.prev.into();
+        }
+        self.ty[parent.get()] = match self.ty(parent) {
+            ObjectType::Array if self.first_child(parent).is_none() => ObjectType::EmptyArray,
+            ObjectType::Structure if self.first_child(parent).is_none() => {
+                ObjectType::EmptyStructure
+            }
+            ty => ty,
+        };
+        // display order has removed node
+        self.clear_sort(parent);
+        self.renumber_after(object.prev.or(object.next), object.prev.is_none(), -1);
+    }
+
+    /// Puts node back between siblings, that it has links to
+    fn relink(&mut self, index: JsonMetadataIndex) {
+        let object = self.get(index);
+        let parent = object.parent.unwrap();
+        match object.prev {
+            Some(prev) => self.next[prev.get()] = Link(index.0),
+            None => self.first_child[parent.get()] = Link(index.0),
+        }
+        if let Some(next) = object.next {
+            self.prev[next.get()] = Link(index.0);
+        }
+        self.ty[parent.get()] = match self.ty(parent) {
+            ObjectType::EmptyArray => ObjectType::Array,
+            ObjectType::EmptyStructure => ObjectType::Structure,
+            ty => ty,
+        };
+        self.clear_sort(parent);
+        self.renumber_after(Some(index), false, 1);
+    }
+
+    /// Adds `delta` to indices of array elements after `index`, or from `index` with `inclusive`
+    fn renumber_after(&mut self, index: Option<JsonMetadataIndex>, inclusive: bool, delta: i64) {
+        let Some(index) = index else {
+            return;
+        };
+        let first = if inclusive {
+            Some(index)
+        } else {
+            self.next(index)
+        };
+        let mut current = first;
+        while let Some(sibling) = current {
+            if self.name_len[sibling.get()] == 0 {
+                let position = &mut self.name_offset_or_index[sibling.get()];
+                *position = (*position as i64 + delta) as u32;
+            }
+            current = self.next(sibling);
+        }
+    }
+
+    /// Node and its ancestors weren't removed by edits
+    pub fn is_attached(&self, index: JsonMetadataIndex) -> bool {
+        self.nearest_attached(index) == index
+    }
+
+    /// Returns `index`, or node, that took place of its removed ancestor: previous sibling or parent
+    pub fn nearest_attached(&self, mut index: JsonMetadataIndex) -> JsonMetadataIndex {
+        loop {
+            let mut removed = None;
+            let mut current = index;
+            while let Some(parent) = self.parent(current) {
+                let linked = match self.prev(current) {
+                    Some(prev) => self.next(prev) == Some(current),
+                    None => self.first_child(parent) == Some(current),
+                };
+                if !linked {
+                    removed = Some(current);
+                }
+                current = parent;
+            }
+            match removed {
+                // previous sibling could be removed later too
+                Some(removed) => index = self.prev(removed).or(self.parent(removed)).unwrap(),
+                None => return index,
+            }
+        }
+    }
+}
+
+/// Value with its key
+fn item_range(structure: &JsonMetadata, index: JsonMetadataIndex) -> Range<usize> {
+    let object = structure.get(index);
+    let start = match object.name_or_index {
+        NameOrIndex::Name { start, .. } => start,
+        NameOrIndex::Index(_) | NameOrIndex::Root => object.source_start,
+    };
+    start..object.source_start + object.source_len
+}
+
+impl ObjectType {
+    pub fn is_scalar(self) -> bool {
+        matches!(
+            self,
+            ObjectType::String | ObjectType::Number | ObjectType::Bool | ObjectType::Null
+        )
+    }
+}
+
+/// Writes through temporary file, which replaces `path`, so mapped original isn't changed
+pub fn write_file_atomically(path: &Path, content: &Document) -> std::io::Result<()> {
+    let mut temporary = path.as_os_str().to_owned();
+    temporary.push(".json_viewer.tmp");
+    let temporary = PathBuf::from(temporary);
+    let mut file = std::io::BufWriter::new(std::fs::File::create(&temporary)?);
+    content.write_to(&mut file)?;
+    file.into_inner()?;
+    if let Ok(metadata) = std::fs::metadata(path) {
+        std::fs::set_permissions(&temporary, metadata.permissions())?;
+    }
+    std::fs::rename(&temporary, path)
+}
+
+/// Checks json grammar of string, number, bool or null
+pub(crate) fn is_scalar(value: &[u8]) -> bool {
+    match value {
+        b"true" | b"false" | b"null" => true,
+        [b'"', inner @ .., b'"'] => {
+            let mut chars = inner.iter();
+            while let Some(&ch) = chars.next() {
+                match ch {
+                    b'"' | 0..0x20 => return false,
+                    b'\\' => match chars.next() {
+                        Some(b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't') => {}
+                        Some(b'u') => {
+                            let hex = chars.by_ref().take(4);
+                            if hex.filter(|ch| ch.is_ascii_hexdigit()).count() != 4 {
+                                return false;
+                            }
+                        }
+                        _ => return false,
+                    },
+                    _ => {}
+                }
+            }
+            true
+        }
+        _ => is_number(value),
+    }
+}
+
+pub(crate) fn is_number(value: &[u8]) -> bool {
+    let digits = |value: &[u8]| value.iter().take_while(|ch| ch.is_ascii_digit()).count();
+    let mut rest = value.strip_prefix(b"-").unwrap_or(value);
+    let integer = digits(rest);
+    if integer == 0 || (integer > 1 && rest[0] == b'0') {
+        return false;
+    }
+    rest = &rest[integer..];
+    if let Some(fraction) = rest.strip_prefix(b".") {
+        let len = digits(fraction);
+        if len == 0 {
+            return false;
+        }
+        rest = &fraction[len..];
+    }
+    if let Some(exponent) = rest.strip_prefix(b"e").or(rest.strip_prefix(b"E")) {
+        let exponent = exponent
+            .strip_prefix(b"+")
+            .or(exponent.strip_prefix(b"-"))
+            .unwrap_or(exponent);
+        let len = digits(exponent);
+        if len == 0 {
+            return false;
+        }
+        rest = &exponent[len..];
+    }
+    rest.is_empty()
+}
@@ -0,0 +1,169 @@
+use anyhow::{Result, bail};
+
+use crate::*;
+
+/// String, which node is root of json decoded from it
+struct Decoded {
+    index: JsonMetadataIndex,
+    /// Range of string, including quotes
+    source_start: usize,
+    source_len: usize,
+}
+
+/// Json inside of string values, browsed like other nodes.
+/// Decoded json is read from its own buffer at positions inside of string, so document isn't changed.
+#[derive(Default)]
+pub struct Embedded {
+    decoded: Vec<Decoded>,
+}
+
+impl Embedded {
+    pub fn is_empty(&self) -> bool {
+        self.decoded.is_empty()
+    }
+
+    /// Decodes string at `index` into json or puts back string, that was decoded into `index`
+    pub fn toggle(
+        &mut self,
+        content: &mut Document,
+        structure: &mut JsonMetadata,
+        index: JsonMetadataIndex,
+    ) -> Result<()> {
+        let object = structure.get(index);
+        if self.decoded.iter().any(|decoded| decoded.index == index) {
+            // strings decoded inside of it are put back with it
+            while let Some(position) = self.decoded.iter().rposition(|decoded| {
+                std::iter::successors(Some(decoded.index), |&node| structure.parent(node))
+                    .any(|node| node == index)
+            }) {
+                let decoded = self.decoded.remove(position);
+                content.remove_overlay(structure.source_start(decoded.index));
+                structure.move_value(decoded.index, decoded.source_start, decoded.source_len);
+                structure.first_child[decoded.index.get()] = Link::NONE;
+                structure.ty[decoded.index.get()] = ObjectType::String;
+            }
+            return Ok(());
+        }
+        if object.ty != ObjectType::String {
+            bail!("only strings with json inside could be decoded");
+        }
+        let Some(json) = decode_embedded_json(&object.source(content)) else {
+            bail!("string doesn't contain json object or array");
+        };
+        // json isn't longer than string without quotes
+        let start = object.source_start + 1;
+        structure.ty[index.get()] = scan_value(&json, 0).map_err(|err| err.at(start))?.0;
+        structure.move_value(index, start, json.len());
+        // children are indexed from decoded json on expand
+        content.add_overlay(start, json);
+        self.decoded.push(Decoded {
+            index,
+            source_start: object.source_start,
+            source_len: object.source_len,
+        });
+        Ok(())
+    }
+}
+
+impl JsonMetadata {
+    /// Sets range of value, keeping its name in place
+    fn move_value(&mut self, index: JsonMetadataIndex, source_start: usize, source_len: usize) {
+        let i = index.get();
+        if self.name_len[i] != 0 {
+            let name_start = self.source_start[i] - self.name_offset_or_index[i] as usize;
+            self.name_offset_or_index[i] = (source_start - name_start) as u32;
+        }
+        self.source_start[i] = source_start;
+        self.source_len[i] = source_len;
+    }
+}
+
+/// `source` is string with quotes
+/// # Returns object or array inside of string without insignificant whitespace
+pub fn decode_embedded_json(source: &[u8]) -> Option<Vec<u8>> {
+    let inner = str::from_utf8(source.get(1..source.len().checked_sub(1)?)?).ok()?;
+    let text = decode_escapes(inner);
+    let text = text.trim_start().as_bytes();
+    if !matches!(text.first(), Some(b'{' | b'[')) {
+        return None;
+    }
+    let mut output = Vec::with_capacity(text.len());
+    let end = compact_value(text, 0, &mut output, 0)?;
+    (skip_whitespace(text, end) == text.len()).then_some(output)
+}
+
+/// Limits recursion of deeply nested input
+const MAX_DEPTH: usize = 512;
+
+/// Checks json grammar and copies value at `cursor` to `output` without whitespace,
+/// parsers expect colons and commas right after values.
+/// # Returns position after value
+fn compact_value(text: &[u8], cursor: usize, output: &mut Vec<u8>, depth: usize) -> Option<usize> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+    let mut cursor = skip_whitespace(text, cursor);
+    match *text.get(cursor)? {
+        open @ (b'{' | b'[') => {
+            let close = if open == b'{' { b'}' } else { b']' };
+            output.push(open);
+            cursor = skip_whitespace(text, cursor + 1);
+            if text.get(cursor) == Some(&close) {
+                output.push(close);
+                return Some(cursor + 1);
+            }
+            loop {
+                if open == b'{' {
+                    cursor = skip_whitespace(text, cursor);
+                    if text.get(cursor) != Some(&b'"') {
+                        return None;
+                    }
+                    cursor = compact_value(text, cursor, output, depth + 1)?;
+                    cursor = skip_whitespace(text, cursor);
+                    if text.get(cursor) != Some(&b':') {
+                        return None;
+                    }
+                    output.push(b':');
+                    cursor += 1;
+                }
+                cursor = compact_value(text, cursor, output, depth + 1)?;
+                cursor = skip_whitespace(text, cursor);
+                match *text.get(cursor)? {
+                    b',' => output.push(b','),
+                    ch if ch == close => {
+                        output.push(close);
+                        return Some(cursor + 1);
+                    }
+                    _ => return None,
+                }
+                cursor += 1;
+            }
+        }
+        b'"' => {
+            let mut escaped = false;
+            let len = text[cursor + 1..].iter().position(|&ch| {
+                let end = ch == b'"' && !escaped;
+                escaped = ch == b'\\' && !escaped;
+                end
+            })? + 2;
+            let string = &text[cursor..cursor + len];
+            if !is_scalar(string) {
+                return None;
+            }
+            output.extend_from_slice(string);
+            Some(cursor + len)
+        }
+        _ => {
+            let len = text[cursor..]
+                .iter()
+                .take_while(|ch| ch.is_ascii_alphanumeric() || matches!(ch, b'-' | b'+' | b'.'))
+                .count();
+            let value = &text[cursor..cursor + len];
+            if !is_scalar(value) {
+                return None;
+            }
+            output.extend_from_slice(value);
+            Some(cursor + len)
+        }
+    }
+}
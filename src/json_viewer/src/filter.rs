@@ -0,0 +1,72 @@
+use crate::*;
+
+impl JsonMetadata {
+    /// Hides all nodes except `matches` and their ancestors, which are expanded to show matches.
+    /// Expansion before the first filter is restored by `clear_filter` and by next `set_filter`.
+    /// Structure should be fully indexed, see `index_all`.
+    pub fn set_filter(&mut self, matches: &[JsonMetadataIndex]) {
+        match &self.expanded_before_filter {
+            Some(before) => restore_expanded(&mut self.expanded, before),
+            None => self.expanded_before_filter = Some(self.expanded.clone()),
+        }
+        let mut shown = vec![false; self.len()];
+        if let Some(root) = shown.first_mut() {
+            *root = true;
+        }
+        for &index in matches {
+            shown[index.get()] = true;
+            let mut current = self.parent(index);
+            while let Some(parent) = current
+                && !(shown[parent.get()] && self.is_expanded(parent))
+            {
+                shown[parent.get()] = true;
+                self.expanded[parent.get()] = true;
+                current = self.parent(parent);
+            }
+        }
+        self.shown = Some(shown);
+    }
+    pub fn clear_filter(&mut self) {
+        self.shown = None;
+        if let Some(before) = self.expanded_before_filter.take() {
+            restore_expanded(&mut self.expanded, &before);
+        }
+    }
+    pub fn is_filtered(&self) -> bool {
+        self.shown.is_some()
+    }
+    /// Expansion, that will be restored when filter is cleared
+    pub fn is_expanded_unfiltered(&self, index: JsonMetadataIndex) -> bool {
+        match &self.expanded_before_filter {
+            Some(before) => before.get(index.get()).copied().unwrap_or(false),
+            None => self.is_expanded(index),
+        }
+    }
+    /// Not hidden by filter, collapsed ancestors could still hide it
+    pub fn is_shown(&self, index: JsonMetadataIndex) -> bool {
+        self.shown.as_ref().is_none_or(|shown| shown[index.get()])
+    }
+    /// Siblings are in order of overview, see `sort_children`
+    pub fn first_shown_child(&self, index: JsonMetadataIndex) -> Option<JsonMetadataIndex> {
+        self.displayed_children(index)
+            .find(|&child| self.is_shown(child))
+    }
+    pub fn next_shown_sibling(&self, index: JsonMetadataIndex) -> Option<JsonMetadataIndex> {
+        std::iter::successors(self.next_displayed(index), |&next| {
+            self.next_displayed(next)
+        })
+        .find(|&next| self.is_shown(next))
+    }
+    pub fn prev_shown_sibling(&self, index: JsonMetadataIndex) -> Option<JsonMetadataIndex> {
+        std::iter::successors(self.prev_displayed(index), |&prev| {
+            self.prev_displayed(prev)
+        })
+        .find(|&prev| self.is_shown(prev))
+    }
+}
+
+/// Nodes indexed while filtered were collapsed
+fn restore_expanded(expanded: &mut [bool], before: &[bool]) {
+    expanded[..before.len()].copy_from_slice(before);
+    expanded[before.len()..].fill(false);
+}
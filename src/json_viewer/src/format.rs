@@ -0,0 +1,62 @@
+use std::io::Write;
+
+use crate::*;
+
+/// Writes `index` subtree as json indented with 2 spaces, with trailing newline.
+/// Indexes the subtree.
+pub fn write_pretty(
+    output: &mut impl Write,
+    content: &Document,
+    structure: &mut JsonMetadata,
+    index: JsonMetadataIndex,
+) -> std::io::Result<()> {
+    structure
+        .index_subtree(content, index)
+        .map_err(std::io::Error::other)?;
+    let mut current = index;
+    let mut depth = 0;
+    loop {
+        let object = structure.get(current);
+        output.write_all("  ".repeat(depth).as_bytes())?;
+        if current != index
+            && let NameOrIndex::Name { start, len } = object.name_or_index
+        {
+            output.write_all(content.token(start..start + len.get() as usize))?;
+            output.write_all(b": ")?;
+        }
+        match object.ty {
+            ObjectType::Array | ObjectType::Structure => {
+                output.write_all(if object.ty == ObjectType::Array {
+                    b"[\n"
+                } else {
+                    b"{\n"
+                })?;
+                current = structure.first_child(current).unwrap();
+                depth += 1;
+                continue;
+            }
+            ObjectType::EmptyArray => output.write_all(b"[]")?,
+            ObjectType::EmptyStructure => output.write_all(b"{}")?,
+            _ => output.write_all(&object.source(content))?,
+        }
+        loop {
+            if current == index {
+                return output.write_all(b"\n");
+            }
+            if let Some(next) = structure.next(current) {
+                output.write_all(b",\n")?;
+                current = next;
+                break;
+            }
+            current = structure.parent(current).unwrap();
+            depth -= 1;
+            output.write_all(b"\n")?;
+            output.write_all("  ".repeat(depth).as_bytes())?;
+            output.write_all(if structure.ty(current) == ObjectType::Array {
+                b"]"
+            } else {
+                b"}"
+            })?;
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::*;
+
+/// Longer strings aren't checked, so frames with huge blobs stay fast
+const MAX_CHECKED_STRING: usize = 64 * 1024;
+/// Characters of decoded base64 text in hint
+const PREVIEW_CHARS: usize = 40;
+
+/// Annotation of scalar value, like date of epoch timestamp.
+/// Result is user content, it should be sanitized before display.
+pub fn value_hint(ty: ObjectType, source: &[u8]) -> Option<String> {
+    match ty {
+        ObjectType::Number => number_hint(str::from_utf8(source).ok()?),
+        ObjectType::String if source.len() <= MAX_CHECKED_STRING => {
+            if decode_embedded_json(source).is_some() {
+                return Some("json, 'x' decodes it".to_owned());
+            }
+            base64_hint(&source[1..source.len() - 1])
+        }
+        _ => None,
+    }
+}
+
+/// Epoch seconds and milliseconds between 2001 and 2286 are shown as UTC dates,
+/// other numbers with 5 or more digits before fraction or exponent get thousands separators
+fn number_hint(number: &str) -> Option<String> {
+    let (sign, digits) = match number.strip_prefix('-') {
+        Some(digits) => ("-", digits),
+        None => ("", number),
+    };
+    let (digits, exponent) = match digits.find(['e', 'E']) {
+        Some(position) => digits.split_at(position),
+        None => (digits, ""),
+    };
+    let (integer, fraction) = match digits.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (digits, None),
+    };
+    if sign.is_empty() && fraction.is_none() && exponent.is_empty() {
+        match integer.len() {
+            10 => return Some(format_epoch(integer.parse().ok()?, None)),
+            13 => {
+                let millis: i64 = integer.parse().ok()?;
+                return Some(format_epoch(millis / 1000, Some(millis % 1000)));
+            }
+            _ => {}
+        }
+    }
+    if integer.len() < 5 {
+        return None;
+    }
+    let mut hint = format!("{sign}{}", group_thousands(integer));
+    if let Some(fraction) = fraction {
+        hint.push('.');
+        hint.push_str(fraction);
+    }
+    hint.push_str(exponent);
+    if fraction.is_none()
+        && (integer.parse::<u64>().is_ok_and(|value| value > 1 << 53) || integer.len() > 19)
+    {
+        // javascript and other parsers, that use f64, round it
+        hint.push_str(", not exact as f64");
+    }
+    Some(hint)
+}
+
+fn group_thousands(digits: &str) -> String {
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// ISO 8601 date in UTC
+fn format_epoch(seconds: i64, millis: Option<i64>) -> String {
+    let days = seconds.div_euclid(86400);
+    let time = seconds.rem_euclid(86400);
+    let (year, month, day) = civil_from_days(days);
+    let mut date = format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    );
+    if let Some(millis) = millis {
+        date.push_str(&format!(".{millis:03}"));
+    }
+    date.push('Z');
+    date
+}
+
+/// Date of proleptic Gregorian calendar from days since 1970-01-01,
+/// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Shown only if decoded data is readable text or known file format,
+/// so words, that happen to be valid base64, don't get hints
+fn base64_hint(text: &[u8]) -> Option<String> {
+    if text.len() < 8 {
+        return None;
+    }
+    let decoded = base64_decode(text)?;
+    if let Ok(decoded_text) = str::from_utf8(&decoded)
+        && !decoded_text.is_empty()
+        && !decoded_text
+            .chars()
+            .any(|ch| ch.is_control() && !matches!(ch, '\n' | '\r' | '\t'))
+    {
+        let mut preview: String = decoded_text.chars().take(PREVIEW_CHARS).collect();
+        if preview.len() < decoded_text.len() {
+            preview.push('…');
+        }
+        return Some(format!("base64: {preview}"));
+    }
+    let format = match decoded.as_slice() {
+        [0x89, b'P', b'N', b'G', ..] => "png",
+        [0xff, 0xd8, 0xff, ..] => "jpeg",
+        [b'G', b'I', b'F', b'8', ..] => "gif",
+        [b'%', b'P', b'D', b'F', ..] => "pdf",
+        [0x1f, 0x8b, ..] => "gzip",
+        [b'P', b'K', 3, 4, ..] => "zip",
+        _ => return None,
+    };
+    Some(format!(
+        "base64: {format}, {} bytes",
+        group_thousands(&decoded.len().to_string())
+    ))
+}
+
+/// Standard and url-safe alphabets, padding is optional
+fn base64_decode(text: &[u8]) -> Option<Vec<u8>> {
+    let unpadded = text
+        .strip_suffix(b"==")
+        .or(text.strip_suffix(b"="))
+        .unwrap_or(text);
+    if unpadded.len() % 4 == 1 || (unpadded.len() != text.len() && !text.len().is_multiple_of(4)) {
+        return None;
+    }
+    let mut output = Vec::with_capacity(unpadded.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for &ch in unpadded {
+        let sextet = match ch {
+            b'A'..=b'Z' => ch - b'A',
+            b'a'..=b'z' => ch - b'a' + 26,
+            b'0'..=b'9' => ch - b'0' + 52,
+            b'+' | b'-' => 62,
+            b'/' | b'_' => 63,
+            _ => return None,
+        };
+        buffer = buffer << 6 | sextet as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            output.push((buffer >> bits) as u8);
+        }
+    }
+    Some(output)
+}
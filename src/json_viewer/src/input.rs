@@ -0,0 +1,56 @@
+use std::{
+    fs::File,
+    io::{IsTerminal, Read},
+    path::Path,
+};
+
+/// Memory maps regular files, so huge documents aren't copied into memory.
+/// Other files(pipes, character devices) are read to the end.
+/// Returned content lives until the end of program.
+pub fn read_input(path: &Path) -> std::io::Result<&'static [u8]> {
+    let mut file = File::open(path)?;
+    let metadata = file.metadata()?;
+    if metadata.is_file() && metadata.len() > 0 {
+        let len = usize::try_from(metadata.len()).unwrap();
+        // SAFETY: mapping is private and read only; it's never unmapped, so slice is valid for 'static.
+        // File modification by other processes while viewing is not supported.
+        unsafe {
+            let ptr = rustix::mm::mmap(
+                std::ptr::null_mut(),
+                len,
+                rustix::mm::ProtFlags::READ,
+                rustix::mm::MapFlags::PRIVATE,
+                &file,
+                0,
+            )?;
+            // only a hint, safe to ignore failure
+            let _ = rustix::mm::madvise(ptr, len, rustix::mm::Advice::Sequential);
+            Ok(std::slice::from_raw_parts(ptr.cast::<u8>(), len))
+        }
+    } else {
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)?;
+        Ok(content.leak())
+    }
+}
+
+/// Reads piped document, fails if stdin is terminal
+pub fn read_stdin() -> std::io::Result<&'static [u8]> {
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Err(std::io::Error::other(
+            "expected file argument or piped input",
+        ));
+    }
+    let mut content = Vec::new();
+    stdin.read_to_end(&mut content)?;
+    Ok(content.leak())
+}
+
+/// Fails for input without document, there is nothing to show
+pub fn ensure_not_empty(content: &[u8]) -> std::io::Result<()> {
+    if content.iter().all(u8::is_ascii_whitespace) {
+        return Err(std::io::Error::other("empty input"));
+    }
+    Ok(())
+}
@@ -0,0 +1,157 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    /// Control + lowercase letter
+    Ctrl(char),
+    Enter,
+    Escape,
+    Backspace,
+    Tab,
+    BackTab,
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Insert,
+    Delete,
+    Mouse(Mouse),
+    /// Unsupported escape sequence or invalid utf-8
+    Unknown,
+}
+
+/// SGR mouse report of left button or wheel, position starts from 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mouse {
+    pub kind: MouseKind,
+    pub column: u16,
+    pub row: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseKind {
+    Press,
+    /// Motion with pressed button
+    Drag,
+    Release,
+    WheelUp,
+    WheelDown,
+}
+
+/// Decodes raw terminal input: utf-8 characters, control characters and CSI/SS3 escape sequences
+pub fn decode_keys(mut input: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    while let Some(&first) = input.first() {
+        let (key, len) = match first {
+            0x1b => decode_escape(input),
+            b'\r' | b'\n' => (Key::Enter, 1),
+            b'\t' => (Key::Tab, 1),
+            0x7f | 0x08 => (Key::Backspace, 1),
+            0x01..=0x1a => (Key::Ctrl((first - 1 + b'a') as char), 1),
+            0x00..=0x1f => (Key::Unknown, 1),
+            _ => decode_char(input),
+        };
+        keys.push(key);
+        input = &input[len..];
+    }
+    keys
+}
+
+fn decode_escape(input: &[u8]) -> (Key, usize) {
+    match input.get(1) {
+        Some(b'[') => {
+            // parameters and intermediate bytes, then final byte
+            let Some(final_position) = input[2..]
+                .iter()
+                .position(|byte| (0x40..=0x7e).contains(byte))
+            else {
+                return (Key::Unknown, input.len());
+            };
+            let params = &input[2..2 + final_position];
+            let key = match (input[2 + final_position], params) {
+                (b'A', _) => Key::Up,
+                (b'B', _) => Key::Down,
+                (b'C', _) => Key::Right,
+                (b'D', _) => Key::Left,
+                (b'H', _) => Key::Home,
+                (b'F', _) => Key::End,
+                (b'Z', _) => Key::BackTab,
+                (b'~', b"1" | b"7") => Key::Home,
+                (b'~', b"2") => Key::Insert,
+                (b'~', b"3") => Key::Delete,
+                (b'~', b"4" | b"8") => Key::End,
+                (b'~', b"5") => Key::PageUp,
+                (b'~', b"6") => Key::PageDown,
+                (b'M' | b'm', [b'<', report @ ..]) => {
+                    decode_mouse(report, input[2 + final_position] == b'M')
+                }
+                _ => Key::Unknown,
+            };
+            (key, 3 + final_position)
+        }
+        Some(b'O') if input.len() >= 3 => {
+            let key = match input[2] {
+                b'A' => Key::Up,
+                b'B' => Key::Down,
+                b'C' => Key::Right,
+                b'D' => Key::Left,
+                b'H' => Key::Home,
+                b'F' => Key::End,
+                _ => Key::Unknown,
+            };
+            (key, 3)
+        }
+        // lone escape or escape before another key(alt + key)
+        _ => (Key::Escape, 1),
+    }
+}
+
+/// `report` is `button;column;row` of `ESC [ < button ; column ; row M`, `m` is release
+fn decode_mouse(report: &[u8], pressed: bool) -> Key {
+    let mut numbers = report
+        .split(|&byte| byte == b';')
+        .map(|number| str::from_utf8(number).ok()?.parse::<u16>().ok());
+    let (Some(Some(button)), Some(Some(column)), Some(Some(row)), None) = (
+        numbers.next(),
+        numbers.next(),
+        numbers.next(),
+        numbers.next(),
+    ) else {
+        return Key::Unknown;
+    };
+    // modifiers(shift, alt, control) are ignored
+    let kind = match (button & !0b11100, pressed) {
+        (0, true) => MouseKind::Press,
+        (0, false) => MouseKind::Release,
+        (32, _) => MouseKind::Drag,
+        (64, _) => MouseKind::WheelUp,
+        (65, _) => MouseKind::WheelDown,
+        _ => return Key::Unknown,
+    };
+    Key::Mouse(Mouse {
+        kind,
+        column: column.saturating_sub(1),
+        row: row.saturating_sub(1),
+    })
+}
+
+fn decode_char(input: &[u8]) -> (Key, usize) {
+    let len = match input[0] {
+        0x00..=0x7f => 1,
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => return (Key::Unknown, 1),
+    };
+    match input
+        .get(..len)
+        .and_then(|bytes| str::from_utf8(bytes).ok())
+        .and_then(|str| str.chars().next())
+    {
+        Some(ch) => (Key::Char(ch), len),
+        None => (Key::Unknown, 1),
+    }
+}
@@ -0,0 +1,368 @@
+use anyhow::{Result, anyhow};
+
+use crate::*;
+
+/// Indexes only top level value and its children, so first frame could be rendered immediately.
+/// Other containers are indexed on first expand, so invalid json inside of them is found then.
+pub fn parse_json_structure_lazy(content: &[u8]) -> Result<JsonMetadata> {
+    index_document(content, false)
+}
+
+/// Indexes top level value, or documents of stream, and their children.
+/// With `deep` all descendants are indexed too.
+pub(crate) fn index_document(content: &[u8], deep: bool) -> Result<JsonMetadata> {
+    let mut structure = JsonMetadata::default();
+    let start = skip_whitespace(content, 0);
+    if start == content.len() {
+        return Ok(structure);
+    }
+    let (ty, source_len) = scan_value(content, start).map_err(|err| err.at(0))?;
+    let after = skip_whitespace(content, start + source_len);
+    if after == content.len() {
+        let root = structure.push(ObjectMeta {
+            name_or_index: NameOrIndex::Root,
+            ty,
+            source_start: start,
+            source_len,
+            parent: None,
+            prev: None,
+            next: None,
+            expanded: true,
+        });
+        structure.index_container_in(content, 0, root, deep)?;
+        return Ok(structure);
+    }
+    // several top level values, like in NDJSON, are elements of synthetic root array
+    let root = structure.push(stream_root(content));
+    let first = structure.push_child(ObjectMeta {
+        name_or_index: NameOrIndex::Index(0),
+        ty,
+        source_start: start,
+        source_len,
+        parent: Some(root),
+        prev: None,
+        next: None,
+        expanded: false,
+    });
+    if deep {
+        structure.index_container_in(content, 0, first, true)?;
+    }
+    structure.index_values(content, 0, root, after, content.len(), deep)?;
+    Ok(structure)
+}
+
+/// Scanned content isn't json, position is of unexpected byte or end of content
+#[derive(Debug)]
+pub(crate) struct InvalidJson(usize);
+
+impl InvalidJson {
+    /// `base` is position of scanned content in document
+    pub(crate) fn at(self, base: usize) -> anyhow::Error {
+        anyhow!("invalid JSON at byte {}", base + self.0)
+    }
+}
+
+/// Container, which children are indexed by `index_values`
+struct Level {
+    container: JsonMetadataIndex,
+    is_object: bool,
+    prev: Option<JsonMetadataIndex>,
+    array_index: u64,
+}
+
+impl JsonMetadata {
+    pub fn is_indexed(&self, index: JsonMetadataIndex) -> bool {
+        match self.ty(index) {
+            ObjectType::Array | ObjectType::Structure => self.first_child(index).is_some(),
+            _ => true,
+        }
+    }
+
+    /// Indexes direct children of container, skipping over nested containers without indexing them.
+    /// Does nothing if children are already indexed.
+    /// On invalid json nothing is indexed.
+    pub fn index_children(&mut self, content: &Document, index: JsonMetadataIndex) -> Result<()> {
+        self.index_container(content, index, false)
+    }
+
+    /// Container isn't indexed, so nothing inside was edited and it's within one piece
+    fn index_container(
+        &mut self,
+        content: &Document,
+        index: JsonMetadataIndex,
+        deep: bool,
+    ) -> Result<()> {
+        if self.is_indexed(index) {
+            return Ok(());
+        }
+        let (base, chunk) = content.chunk(self.source_start(index));
+        self.index_container_in(chunk, base, index, deep)
+    }
+
+    /// `content` is part of document at `base` position, that has the container
+    fn index_container_in(
+        &mut self,
+        content: &[u8],
+        base: usize,
+        index: JsonMetadataIndex,
+        deep: bool,
+    ) -> Result<()> {
+        if self.is_indexed(index) {
+            return Ok(());
+        }
+        let container = self.get(index);
+        let start = container.source_start - base;
+        self.index_values(
+            content,
+            base,
+            index,
+            start + 1,
+            start + container.source_len - 1,
+            deep,
+        )
+    }
+
+    /// Indexes values between `cursor` and `end` as children of container after its indexed children.
+    /// Whitespace is allowed around names, colons and commas.
+    /// With `deep` nested containers are indexed too and their ends are found while indexing them,
+    /// otherwise they are skipped over.
+    /// `content` is part of document at `base` position, cursors are relative to it.
+    /// On invalid json nodes pushed by it are dropped.
+    fn index_values(
+        &mut self,
+        content: &[u8],
+        base: usize,
+        index: JsonMetadataIndex,
+        cursor: usize,
+        end: usize,
+        deep: bool,
+    ) -> Result<()> {
+        let len = self.len();
+        let prev = self.children(index).last();
+        let array_index = match prev.map(|prev| self.get(prev).name_or_index) {
+            Some(NameOrIndex::Index(index)) => index + 1,
+            _ => 0,
+        };
+        let level = Level {
+            container: index,
+            is_object: self.ty(index) == ObjectType::Structure,
+            prev,
+            array_index,
+        };
+        let result = self.scan_values(content, base, level, cursor, end, deep);
+        if result.is_err() {
+            self.truncate(len);
+            match prev {
+                Some(prev) => self.next[prev.get()] = Link::NONE,
+                None => self.first_child[index.get()] = Link::NONE,
+            }
+        }
+        result.map_err(|err| err.at(base))
+    }
+
+    /// Pushes nodes for `index_values`, `level` is the container at `cursor`
+    fn scan_values(
+        &mut self,
+        content: &[u8],
+        base: usize,
+        level: Level,
+        mut cursor: usize,
+        end: usize,
+        deep: bool,
+    ) -> Result<(), InvalidJson> {
+        // innermost last, nested containers are closed by their brackets
+        let mut levels = vec![level];
+        loop {
+            cursor = skip_whitespace(content, cursor);
+            if levels.len() > 1 && matches!(content.get(cursor), Some(b']' | b'}')) {
+                let container = levels.pop().unwrap().container;
+                cursor += 1;
+                self.source_len[container.get()] = base + cursor - self.source_start(container);
+                cursor = skip_comma(content, cursor);
+                continue;
+            }
+            if levels.len() == 1 && cursor >= end {
+                break;
+            }
+            let level = levels.last_mut().unwrap();
+            let name_or_index = if level.is_object {
+                if content.get(cursor) != Some(&b'"') {
+                    return Err(InvalidJson(cursor));
+                }
+                let (_, len) = scan_value(content, cursor)?;
+                let name = NameOrIndex::Name {
+                    start: base + cursor,
+                    len: NonZeroU32::new(u32::try_from(len).unwrap()).unwrap(),
+                };
+                cursor = skip_whitespace(content, cursor + len);
+                if content.get(cursor) != Some(&b':') {
+                    return Err(InvalidJson(cursor));
+                }
+                cursor = skip_whitespace(content, cursor + 1);
+                name
+            } else {
+                level.array_index += 1;
+                NameOrIndex::Index(level.array_index - 1)
+            };
+            let (ty, source_len) = match container_type(content, cursor) {
+                // length is set on closing bracket
+                Some(ty @ (ObjectType::Array | ObjectType::Structure)) if deep => (ty, 0),
+                _ => scan_value(content, cursor)?,
+            };
+            let child = self.push_child(ObjectMeta {
+                name_or_index,
+                ty,
+                source_start: base + cursor,
+                source_len,
+                parent: Some(level.container),
+                prev: level.prev,
+                next: None,
+                expanded: false,
+            });
+            level.prev = Some(child);
+            if source_len == 0 {
+                levels.push(Level {
+                    container: child,
+                    is_object: ty == ObjectType::Structure,
+                    prev: None,
+                    array_index: 0,
+                });
+                cursor += 1;
+            } else {
+                cursor = skip_comma(content, cursor + source_len);
+            }
+        }
+        Ok(())
+    }
+
+    /// Indexes all descendants of `index`
+    /// On invalid json containers before it stay indexed.
+    pub fn index_subtree(&mut self, content: &Document, index: JsonMetadataIndex) -> Result<()> {
+        let mut pending = vec![index];
+        while let Some(index) = pending.pop() {
+            if self.is_indexed(index) {
+                // popped in document order
+                let children: Vec<_> = self.children(index).collect();
+                pending.extend(children.into_iter().rev());
+            } else {
+                self.index_container(content, index, true)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn index_all(&mut self, content: &Document) -> Result<()> {
+        if self.is_empty() {
+            return Ok(());
+        }
+        self.index_subtree(content, JsonMetadataIndex::ROOT)
+    }
+}
+
+/// Synthetic array, that spans whole content and has documents of stream as elements
+pub(crate) fn stream_root(content: &[u8]) -> ObjectMeta {
+    ObjectMeta {
+        name_or_index: NameOrIndex::Root,
+        ty: ObjectType::Array,
+        source_start: 0,
+        source_len: content.len(),
+        parent: None,
+        prev: None,
+        next: None,
+        expanded: true,
+    }
+}
+
+pub(crate) fn skip_whitespace(content: &[u8], mut cursor: usize) -> usize {
+    while content.get(cursor).is_some_and(|c| c.is_ascii_whitespace()) {
+        cursor += 1;
+    }
+    cursor
+}
+
+/// Skips whitespace and comma after value
+fn skip_comma(content: &[u8], cursor: usize) -> usize {
+    let cursor = skip_whitespace(content, cursor);
+    match content.get(cursor) {
+        Some(b',') => cursor + 1,
+        _ => cursor,
+    }
+}
+
+/// Finds type and length of value starting at `cursor` without indexing it.
+/// Containers are only checked for balanced brackets, their values are checked when indexed.
+pub(crate) fn scan_value(
+    content: &[u8],
+    cursor: usize,
+) -> Result<(ObjectType, usize), InvalidJson> {
+    let literal = |literal: &[u8], ty| match content[cursor..].starts_with(literal) {
+        true => Ok((ty, literal.len())),
+        false => Err(InvalidJson(cursor)),
+    };
+    match content.get(cursor) {
+        Some(b'"') => {
+            let len =
+                find_escaped_string_length(unsafe { str::from_utf8_unchecked(&content[cursor..]) })
+                    .ok_or(InvalidJson(content.len()))?;
+            Ok((ObjectType::String, len))
+        }
+        Some(b'-' | b'0'..=b'9') => Ok((ObjectType::Number, number_length(content, cursor))),
+        Some(b'n') => literal(b"null", ObjectType::Null),
+        Some(b't') => literal(b"true", ObjectType::Bool),
+        Some(b'f') => literal(b"false", ObjectType::Bool),
+        Some(b'[' | b'{') => Ok((
+            container_type(content, cursor).unwrap(),
+            skip_balanced(content, cursor)?,
+        )),
+        _ => Err(InvalidJson(cursor)),
+    }
+}
+
+/// Type of container, that starts at `cursor`
+fn container_type(content: &[u8], cursor: usize) -> Option<ObjectType> {
+    let is_empty = || {
+        matches!(
+            content.get(skip_whitespace(content, cursor + 1)),
+            Some(b']' | b'}')
+        )
+    };
+    match content.get(cursor)? {
+        b'[' if is_empty() => Some(ObjectType::EmptyArray),
+        b'[' => Some(ObjectType::Array),
+        b'{' if is_empty() => Some(ObjectType::EmptyStructure),
+        b'{' => Some(ObjectType::Structure),
+        _ => None,
+    }
+}
+
+/// Gets opening bracket position and returns bytes count to matching closing bracket(including it)
+fn skip_balanced(content: &[u8], start: usize) -> Result<usize, InvalidJson> {
+    let byte = |cursor: usize| content.get(cursor).ok_or(InvalidJson(content.len()));
+    let mut depth = 0usize;
+    let mut cursor = start;
+    loop {
+        match byte(cursor)? {
+            b'[' | b'{' => depth += 1,
+            b']' | b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(cursor + 1 - start);
+                }
+            }
+            b'"' => {
+                let mut last_backslash = false;
+                cursor += 1;
+                loop {
+                    match byte(cursor)? {
+                        b'\\' if !last_backslash => last_backslash = true,
+                        b'"' if !last_backslash => break,
+                        _ => last_backslash = false,
+                    }
+                    cursor += 1;
+                }
+            }
+            _ => {}
+        }
+        cursor += 1;
+    }
+}
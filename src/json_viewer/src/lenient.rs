@@ -0,0 +1,326 @@
+use anyhow::{Result, bail};
+
+/// Converts JSON5/JSONC-like input to strict JSON.
+/// Comments are removed, trailing commas dropped, single quoted strings and unquoted keys are double quoted.
+/// JSON5 numbers, like `0x1F`, `.5` and `+1`, are written as JSON numbers. Other bytes are copied as is.
+/// `Infinity` and `NaN` values can't be represented in JSON and other bare words aren't values,
+/// so they are errors with line and column.
+pub fn normalize_lenient(content: &[u8]) -> Result<Vec<u8>> {
+    let mut output = Vec::with_capacity(content.len());
+    let mut cursor = 0;
+    while let Some(&ch) = content.get(cursor) {
+        match ch {
+            b'"' => {
+                let end = string_end(content, cursor, b'"');
+                output.extend_from_slice(&content[cursor..end]);
+                cursor = skip_to_colon(content, end);
+            }
+            b'\'' => {
+                let end = string_end(content, cursor, b'\'');
+                let inner_end = if end > cursor + 1 && content[end - 1] == b'\'' {
+                    end - 1
+                } else {
+                    end
+                };
+                output.push(b'"');
+                let mut escaped = false;
+                for &ch in &content[cursor + 1..inner_end] {
+                    match ch {
+                        b'\\' if !escaped => {
+                            escaped = true;
+                            continue;
+                        }
+                        b'\'' if escaped => output.push(b'\''),
+                        b'"' if !escaped => output.extend_from_slice(b"\\\""),
+                        ch if escaped => output.extend_from_slice(&[b'\\', ch]),
+                        ch => output.push(ch),
+                    }
+                    escaped = false;
+                }
+                output.push(b'"');
+                cursor = skip_to_colon(content, end);
+            }
+            b'/' if matches!(content.get(cursor + 1), Some(b'/' | b'*')) => {
+                cursor = comment_end(content, cursor);
+            }
+            b',' => {
+                let next = skip_insignificant(content, cursor + 1);
+                if !matches!(content.get(next), Some(b']' | b'}')) {
+                    // parser expects comma right after value
+                    while output.last().is_some_and(u8::is_ascii_whitespace) {
+                        output.pop();
+                    }
+                    output.push(b',');
+                }
+                cursor += 1;
+            }
+            ch if is_identifier_char(ch) && !ch.is_ascii_digit() => {
+                let end = cursor
+                    + content[cursor..]
+                        .iter()
+                        .take_while(|&&ch| is_identifier_char(ch))
+                        .count();
+                let word = &content[cursor..end];
+                let is_key = content.get(skip_insignificant(content, end)) == Some(&b':');
+                if is_key {
+                    output.push(b'"');
+                    output.extend_from_slice(word);
+                    output.push(b'"');
+                    cursor = skip_to_colon(content, end);
+                } else if matches!(word, b"true" | b"false" | b"null") {
+                    output.extend_from_slice(word);
+                    cursor = end;
+                } else if matches!(word, b"Infinity" | b"NaN") {
+                    not_representable(content, cursor, word)?;
+                } else {
+                    let (line, column) = line_and_column(content, cursor);
+                    bail!(
+                        "unexpected {} at line {line}, column {column}",
+                        String::from_utf8_lossy(word)
+                    );
+                }
+            }
+            b'-' | b'+' if content[cursor + 1..].starts_with(b"Infinity") => {
+                not_representable(content, cursor, &content[cursor..cursor + 9])?;
+            }
+            ch if ch.is_ascii_digit() || matches!(ch, b'-' | b'+' | b'.') => {
+                // signs are only at start and in exponent, other words are invalid numbers
+                let end = cursor
+                    + content[cursor..]
+                        .iter()
+                        .take_while(|&&ch| {
+                            is_identifier_char(ch) || matches!(ch, b'-' | b'+' | b'.')
+                        })
+                        .count();
+                let word = &content[cursor..end];
+                let Some(number) = json_number(word) else {
+                    let (line, column) = line_and_column(content, cursor);
+                    bail!(
+                        "invalid number {} at line {line}, column {column}",
+                        String::from_utf8_lossy(word)
+                    );
+                };
+                output.extend_from_slice(&number);
+                cursor = end;
+            }
+            ch => {
+                output.push(ch);
+                cursor += 1;
+            }
+        }
+    }
+    Ok(output)
+}
+
+fn not_representable(content: &[u8], position: usize, value: &[u8]) -> Result<()> {
+    let (line, column) = line_and_column(content, position);
+    bail!(
+        "{} at line {line}, column {column} can't be represented in JSON",
+        String::from_utf8_lossy(value),
+    )
+}
+
+/// Both start from 1
+fn line_and_column(content: &[u8], position: usize) -> (usize, usize) {
+    let before = &content[..position];
+    let line = before.iter().filter(|&&ch| ch == b'\n').count() + 1;
+    let line_start = before
+        .iter()
+        .rposition(|&ch| ch == b'\n')
+        .map_or(0, |newline| newline + 1);
+    (line, position - line_start + 1)
+}
+
+/// JSON5 number as JSON number: hexadecimal is written as decimal, leading `+` is dropped
+/// and dot without digits on one side gets zero or is dropped.
+/// # Returns `None` if `word` isn't a number
+fn json_number(word: &[u8]) -> Option<Vec<u8>> {
+    let (sign, unsigned) = match word.split_first()? {
+        (b'-', rest) => ("-", rest),
+        (b'+', rest) => ("", rest),
+        _ => ("", word),
+    };
+    let all_digits = |part: &[u8], radix| part.iter().all(|ch| (*ch as char).is_digit(radix));
+    if let Some(hex) = unsigned
+        .strip_prefix(b"0x")
+        .or_else(|| unsigned.strip_prefix(b"0X"))
+    {
+        if hex.is_empty() || !all_digits(hex, 16) {
+            return None;
+        }
+        let value = u128::from_str_radix(str::from_utf8(hex).ok()?, 16).ok()?;
+        return Some(format!("{sign}{value}").into_bytes());
+    }
+    let (mantissa, exponent) = match unsigned.iter().position(|&ch| matches!(ch, b'e' | b'E')) {
+        Some(position) => unsigned.split_at(position),
+        None => (unsigned, &b""[..]),
+    };
+    let (integer, fraction) = match mantissa.iter().position(|&ch| ch == b'.') {
+        Some(position) => (&mantissa[..position], &mantissa[position + 1..]),
+        None => (mantissa, &b""[..]),
+    };
+    let exponent_digits = match exponent.get(1) {
+        Some(b'+' | b'-') => &exponent[2..],
+        _ => exponent.get(1..).unwrap_or_default(),
+    };
+    if (integer.is_empty() && fraction.is_empty())
+        || !all_digits(integer, 10)
+        || !all_digits(fraction, 10)
+        || (!exponent.is_empty()
+            && (exponent_digits.is_empty() || !all_digits(exponent_digits, 10)))
+    {
+        return None;
+    }
+    let mut number = sign.as_bytes().to_vec();
+    number.extend_from_slice(if integer.is_empty() { b"0" } else { integer });
+    if !fraction.is_empty() {
+        number.push(b'.');
+        number.extend_from_slice(fraction);
+    }
+    number.extend_from_slice(exponent);
+    Some(number)
+}
+
+fn is_identifier_char(ch: u8) -> bool {
+    ch.is_ascii_alphanumeric() || ch == b'_' || ch == b'$'
+}
+
+/// # Returns position after closing quote or end of content
+fn string_end(content: &[u8], start: usize, quote: u8) -> usize {
+    let mut escaped = false;
+    for (offset, &ch) in content[start + 1..].iter().enumerate() {
+        match ch {
+            b'\\' if !escaped => escaped = true,
+            ch if ch == quote && !escaped => return start + 1 + offset + 1,
+            _ => escaped = false,
+        }
+    }
+    content.len()
+}
+
+/// Parser expects colon right after key
+fn skip_to_colon(content: &[u8], cursor: usize) -> usize {
+    let next = skip_insignificant(content, cursor);
+    if content.get(next) == Some(&b':') {
+        next
+    } else {
+        cursor
+    }
+}
+
+/// `start` is at the first slash
+fn comment_end(content: &[u8], start: usize) -> usize {
+    if content[start + 1] == b'/' {
+        content[start..]
+            .iter()
+            .position(|&ch| ch == b'\n')
+            .map_or(content.len(), |offset| start + offset)
+    } else {
+        content[start + 2..]
+            .windows(2)
+            .position(|window| window == b"*/")
+            .map_or(content.len(), |offset| start + 2 + offset + 2)
+    }
+}
+
+fn skip_insignificant(content: &[u8], mut cursor: usize) -> usize {
+    loop {
+        match content.get(cursor) {
+            Some(ch) if ch.is_ascii_whitespace() => cursor += 1,
+            Some(b'/') if matches!(content.get(cursor + 1), Some(b'/' | b'*')) => {
+                cursor = comment_end(content, cursor);
+            }
+            _ => return cursor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(content: &str) -> String {
+        String::from_utf8(normalize_lenient(content.as_bytes()).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn comments_are_removed() {
+        assert_eq!(
+            normalize("// header\n{\"a\": /* inline */ 1 // tail\n}"),
+            "\n{\"a\":  1 \n}"
+        );
+        assert_eq!(normalize("[1, /* unterminated"), "[1, ");
+        // slashes inside strings aren't comments
+        assert_eq!(
+            normalize(r#"["http://host", '/*']"#),
+            r#"["http://host", "/*"]"#
+        );
+    }
+
+    #[test]
+    fn single_quoted_strings_are_double_quoted() {
+        assert_eq!(
+            normalize(r#"{'key': 'it\'s "quoted"'}"#),
+            r#"{"key": "it's \"quoted\""}"#
+        );
+        assert_eq!(normalize(r"['a\nb', '\\']"), r#"["a\nb", "\\"]"#);
+    }
+
+    #[test]
+    fn trailing_commas_are_dropped() {
+        assert_eq!(normalize("[1, 2,]"), "[1, 2]");
+        assert_eq!(normalize("{\"a\": [1,\n],\n}"), "{\"a\": [1\n]\n}");
+        assert_eq!(normalize("[1, // last\n]"), "[1 \n]");
+    }
+
+    #[test]
+    fn unquoted_keys_are_quoted() {
+        assert_eq!(
+            normalize("{key: 1, $id_2 : true, n: null}"),
+            r#"{"key": 1, "$id_2": true, "n": null}"#
+        );
+        // like in JSON5, these are names of keys, not values
+        assert_eq!(normalize("{NaN: 1}"), r#"{"NaN": 1}"#);
+    }
+
+    #[test]
+    fn numbers_are_copied() {
+        assert_eq!(normalize("[-1.5e+3, 2E-2, 0]"), "[-1.5e+3, 2E-2, 0]");
+    }
+
+    #[test]
+    fn json5_numbers_are_converted() {
+        assert_eq!(normalize("[0x1F, -0Xff]"), "[31, -255]");
+        assert_eq!(normalize("[.5, -.5e2, 5., +1]"), "[0.5, -0.5e2, 5, 1]");
+    }
+
+    #[test]
+    fn unknown_words_and_invalid_numbers_are_rejected() {
+        for (content, error) in [
+            ("[foo]", "unexpected foo at line 1, column 2"),
+            ("{\n  \"a\": True\n}", "unexpected True at line 2, column 8"),
+            ("[0x]", "invalid number 0x at line 1, column 2"),
+            ("[1.2.3, 1e]", "invalid number 1.2.3 at line 1, column 2"),
+            ("[12ab]", "invalid number 12ab at line 1, column 2"),
+        ] {
+            let err = normalize_lenient(content.as_bytes()).unwrap_err();
+            assert_eq!(err.to_string(), error);
+        }
+    }
+
+    #[test]
+    fn infinity_and_nan_are_rejected_with_position() {
+        for (content, error) in [
+            ("[Infinity]", "Infinity at line 1, column 2"),
+            ("{\n  \"a\": NaN\n}", "NaN at line 2, column 8"),
+            ("[1,\n -Infinity]", "-Infinity at line 2, column 2"),
+            ("[+Infinity]", "+Infinity at line 1, column 2"),
+        ] {
+            let err = normalize_lenient(content.as_bytes()).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("{error} can't be represented in JSON")
+            );
+        }
+    }
+}
@@ -1,7 +1,79 @@
-use std::{
-    num::NonZeroU32,
-    ops::{Index, IndexMut},
-};
+use std::{borrow::Cow, collections::HashMap, num::NonZeroU32};
+
+mod clipboard;
+pub use clipboard::*;
+
+mod data;
+pub use data::*;
+
+mod diff;
+pub use diff::*;
+
+mod diff_view;
+pub use diff_view::*;
+
+mod document;
+pub use document::*;
+
+mod edit;
+pub use edit::*;
+
+mod embedded;
+pub use embedded::*;
+
+mod filter;
+
+mod format;
+pub use format::*;
+
+mod hints;
+pub use hints::*;
+
+mod input;
+pub use input::*;
+
+mod keys;
+pub use keys::*;
+
+mod lazy;
+pub use lazy::*;
+
+mod lenient;
+pub use lenient::*;
+
+mod outline;
+
+mod path;
+pub use path::*;
+
+mod schema;
+pub use schema::*;
+
+mod screen;
+pub use screen::*;
+
+mod search;
+pub use search::*;
+
+mod session;
+pub use session::*;
+
+mod sort;
+
+mod table;
+pub use table::*;
+
+mod terminal;
+pub use terminal::*;
+
+mod text;
+pub use text::*;
+
+mod theme;
+pub use theme::*;
+
+mod viewer;
+pub use viewer::*;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ObjectType {
@@ -17,8 +89,13 @@ pub enum ObjectType {
 
 #[derive(Debug, Clone, Copy)]
 pub enum NameOrIndex {
-    Name { start: usize, len: NonZeroU32 },
+    Name {
+        start: usize,
+        len: NonZeroU32,
+    },
     Index(u64),
+    /// Top level value, or synthetic root array with documents of stream as elements
+    Root,
 }
 const _: () = assert!(size_of::<NameOrIndex>() == 2 * size_of::<u64>());
 
@@ -34,8 +111,23 @@ pub struct ObjectMeta {
 
     pub expanded: bool,
 }
+impl ObjectMeta {
+    /// Key name without quotes
+    pub fn name<'a>(&self, content: &'a Document) -> Option<&'a [u8]> {
+        match self.name_or_index {
+            NameOrIndex::Name { start, len } => {
+                Some(content.token(start + 1..start + len.get() as usize - 1))
+            }
+            NameOrIndex::Index(_) | NameOrIndex::Root => None,
+        }
+    }
+    /// Borrowed, unless edited container spans several pieces of document
+    pub fn source<'a>(&self, content: &'a Document) -> Cow<'a, [u8]> {
+        content.get(self.source_start..self.source_start + self.source_len)
+    }
+}
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct JsonMetadataIndex(pub u32);
 impl JsonMetadataIndex {
     pub const ROOT: Self = JsonMetadataIndex(0);
@@ -48,340 +140,373 @@ impl JsonMetadataIndex {
     }
 }
 
+/// Nodes stored as struct of arrays to keep huge documents small(42 bytes per node).
+/// Depth isn't stored, it's computed from parent links when needed.
+///
+/// Children of containers could be indexed lazily(see `index_children`), so indices aren't in document order,
+/// but `source_start` is.
 #[derive(Default)]
 pub struct JsonMetadata {
-    pub list: Vec<ObjectMeta>,
+    ty: Vec<ObjectType>,
+    expanded: Vec<bool>,
+    source_start: Vec<usize>,
+    source_len: Vec<usize>,
+    /// Distance from name start to `source_start` or index in array
+    name_offset_or_index: Vec<u32>,
+    /// Zero for array elements
+    name_len: Vec<u32>,
+    parent: Vec<Link>,
+    prev: Vec<Link>,
+    next: Vec<Link>,
+    /// None for scalars, empty containers and containers, which children aren't indexed yet
+    first_child: Vec<Link>,
+    /// Nodes kept by filter, `None` if there is no filter
+    shown: Option<Vec<bool>>,
+    /// Expansion before filter expanded ancestors of matches, restored when filter is cleared
+    expanded_before_filter: Option<Vec<bool>>,
+    /// Children of sorted containers in display order
+    sorted: HashMap<JsonMetadataIndex, Vec<JsonMetadataIndex>>,
+    /// Positions of children of sorted containers in display order
+    sorted_position: HashMap<JsonMetadataIndex, u32>,
 }
+
+/// `Option<JsonMetadataIndex>` in 4 bytes
+#[derive(Debug, Clone, Copy)]
+struct Link(u32);
+impl Link {
+    const NONE: Self = Link(u32::MAX);
+
+    fn get(self) -> Option<JsonMetadataIndex> {
+        (self.0 != u32::MAX).then_some(JsonMetadataIndex(self.0))
+    }
+}
+impl From<Option<JsonMetadataIndex>> for Link {
+    fn from(value: Option<JsonMetadataIndex>) -> Self {
+        value.map_or(Link::NONE, |index| Link(index.0))
+    }
+}
+
 impl JsonMetadata {
     fn push(&mut self, value: ObjectMeta) -> JsonMetadataIndex {
-        let i = self.list.len();
-        self.list.push(value);
+        let i = self.len();
+        assert!(i < u32::MAX as usize, "too many nodes");
+        let (name_offset_or_index, name_len) = match value.name_or_index {
+            NameOrIndex::Name { start, len } => (
+                u32::try_from(value.source_start - start).unwrap(),
+                len.get(),
+            ),
+            NameOrIndex::Index(index) => (u32::try_from(index).unwrap(), 0),
+            // told apart by missing parent
+            NameOrIndex::Root => (0, 0),
+        };
+        self.ty.push(value.ty);
+        self.expanded.push(value.expanded);
+        self.source_start.push(value.source_start);
+        self.source_len.push(value.source_len);
+        self.name_offset_or_index.push(name_offset_or_index);
+        self.name_len.push(name_len);
+        self.parent.push(value.parent.into());
+        self.prev.push(value.prev.into());
+        self.next.push(value.next.into());
+        self.first_child.push(Link::NONE);
+        if let Some(shown) = &mut self.shown {
+            shown.push(false);
+        }
         JsonMetadataIndex::new(i)
     }
+    /// Pushes node and links it with `prev` sibling or `parent`
+    fn push_child(&mut self, value: ObjectMeta) -> JsonMetadataIndex {
+        let new_index = self.push(value);
+        if let Some(prev) = value.prev {
+            assert_ne!(prev, new_index);
+            self.next[prev.get()] = Link(new_index.0);
+        } else if let Some(parent) = value.parent {
+            self.first_child[parent.get()] = Link(new_index.0);
+        }
+        new_index
+    }
+    /// Drops nodes pushed after first `len`, links to them should be cleared by caller
+    fn truncate(&mut self, len: usize) {
+        self.ty.truncate(len);
+        self.expanded.truncate(len);
+        self.source_start.truncate(len);
+        self.source_len.truncate(len);
+        self.name_offset_or_index.truncate(len);
+        self.name_len.truncate(len);
+        self.parent.truncate(len);
+        self.prev.truncate(len);
+        self.next.truncate(len);
+        self.first_child.truncate(len);
+        if let Some(shown) = &mut self.shown {
+            shown.truncate(len);
+        }
+    }
+    fn shrink_to_fit(&mut self) {
+        self.ty.shrink_to_fit();
+        self.expanded.shrink_to_fit();
+        self.source_start.shrink_to_fit();
+        self.source_len.shrink_to_fit();
+        self.name_offset_or_index.shrink_to_fit();
+        self.name_len.shrink_to_fit();
+        self.parent.shrink_to_fit();
+        self.prev.shrink_to_fit();
+        self.next.shrink_to_fit();
+        self.first_child.shrink_to_fit();
+        if let Some(shown) = &mut self.shown {
+            shown.shrink_to_fit();
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.ty.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.ty.is_empty()
+    }
+    /// Bytes used by node storage
+    pub fn memory_usage(&self) -> usize {
+        self.ty.capacity() * size_of::<ObjectType>()
+            + self.expanded.capacity() * size_of::<bool>()
+            + self.source_start.capacity() * size_of::<usize>()
+            + self.source_len.capacity() * size_of::<usize>()
+            + self.name_offset_or_index.capacity() * size_of::<u32>()
+            + self.name_len.capacity() * size_of::<u32>()
+            + (self.parent.capacity()
+                + self.prev.capacity()
+                + self.next.capacity()
+                + self.first_child.capacity())
+                * size_of::<Link>()
+            + self.shown.as_ref().map_or(0, Vec::capacity) * size_of::<bool>()
+            + self
+                .expanded_before_filter
+                .as_ref()
+                .map_or(0, Vec::capacity)
+                * size_of::<bool>()
+            + self.sorted_position.capacity()
+                * (size_of::<JsonMetadataIndex>() * 2 + size_of::<u32>())
+    }
+    pub fn get(&self, index: JsonMetadataIndex) -> ObjectMeta {
+        let i = index.get();
+        let name_or_index = match NonZeroU32::new(self.name_len[i]) {
+            Some(len) => NameOrIndex::Name {
+                start: self.source_start[i] - self.name_offset_or_index[i] as usize,
+                len,
+            },
+            None if self.parent[i].get().is_none() => NameOrIndex::Root,
+            None => NameOrIndex::Index(self.name_offset_or_index[i] as u64),
+        };
+        ObjectMeta {
+            name_or_index,
+            ty: self.ty[i],
+            source_start: self.source_start[i],
+            source_len: self.source_len[i],
+            parent: self.parent[i].get(),
+            prev: self.prev[i].get(),
+            next: self.next[i].get(),
+            expanded: self.expanded[i],
+        }
+    }
+    pub fn ty(&self, index: JsonMetadataIndex) -> ObjectType {
+        self.ty[index.get()]
+    }
+    /// Also position in document order
+    pub fn source_start(&self, index: JsonMetadataIndex) -> usize {
+        self.source_start[index.get()]
+    }
+    pub fn parent(&self, index: JsonMetadataIndex) -> Option<JsonMetadataIndex> {
+        self.parent[index.get()].get()
+    }
+    pub fn prev(&self, index: JsonMetadataIndex) -> Option<JsonMetadataIndex> {
+        self.prev[index.get()].get()
+    }
+    pub fn next(&self, index: JsonMetadataIndex) -> Option<JsonMetadataIndex> {
+        self.next[index.get()].get()
+    }
+    pub fn is_expanded(&self, index: JsonMetadataIndex) -> bool {
+        self.expanded[index.get()]
+    }
+    /// Indexes children on first expand, container with invalid json stays collapsed
+    pub fn set_expanded(
+        &mut self,
+        content: &Document,
+        index: JsonMetadataIndex,
+        expanded: bool,
+    ) -> anyhow::Result<()> {
+        if expanded {
+            self.index_children(content, index)?;
+        }
+        self.expanded[index.get()] = expanded;
+        Ok(())
+    }
+    pub fn expand(&mut self, index: JsonMetadataIndex) {
+        // see `set_expanded` for containers, that aren't indexed
+        self.expanded[index.get()] = true;
+    }
+    pub fn collapse(&mut self, index: JsonMetadataIndex) {
+        self.expanded[index.get()] = false;
+    }
+    pub fn toggle_expanded(
+        &mut self,
+        content: &Document,
+        index: JsonMetadataIndex,
+    ) -> anyhow::Result<()> {
+        self.set_expanded(content, index, !self.is_expanded(index))
+    }
+    /// Next node in overview, which shows expanded containers and nodes kept by filter
     pub fn next_visible(&self, index: JsonMetadataIndex) -> Option<JsonMetadataIndex> {
-        let object = self[index];
-        match object.ty {
-            ObjectType::Array | ObjectType::Structure if object.expanded => {
-                Some(JsonMetadataIndex::new(index.get() + 1))
-            }
-            _ => self.next_no_children(index),
+        if self.is_expanded(index)
+            && let Some(first_child) = self.first_shown_child(index)
+        {
+            Some(first_child)
+        } else {
+            self.next_no_children(index)
         }
     }
     fn next_no_children(&self, index: JsonMetadataIndex) -> Option<JsonMetadataIndex> {
-        let object = self[index];
-        if let Some(next) = object.next {
+        if let Some(next) = self.next_shown_sibling(index) {
             Some(next)
         } else {
-            self.next_no_children(object.parent?)
+            self.next_no_children(self.parent(index)?)
         }
     }
+    /// None if there are no children or they aren't indexed yet
+    pub fn first_child(&self, index: JsonMetadataIndex) -> Option<JsonMetadataIndex> {
+        self.first_child[index.get()].get()
+    }
+    pub fn children(
+        &self,
+        index: JsonMetadataIndex,
+    ) -> impl Iterator<Item = JsonMetadataIndex> + '_ {
+        std::iter::successors(self.first_child(index), |&child| self.next(child))
+    }
+    /// Indexed descendants in document order
+    pub fn descendants(
+        &self,
+        index: JsonMetadataIndex,
+    ) -> impl Iterator<Item = JsonMetadataIndex> + '_ {
+        let mut current = self.first_child(index);
+        std::iter::from_fn(move || {
+            let item = current?;
+            current = self.next_in_subtree(index, item, false);
+            Some(item)
+        })
+    }
+    /// Node after `item` in document order in `index` subtree, optionally skipping children of `item`
+    fn next_in_subtree(
+        &self,
+        index: JsonMetadataIndex,
+        item: JsonMetadataIndex,
+        skip_children: bool,
+    ) -> Option<JsonMetadataIndex> {
+        if !skip_children && let Some(first_child) = self.first_child(item) {
+            return Some(first_child);
+        }
+        let mut ancestor = item;
+        while ancestor != index {
+            if let Some(next) = self.next(ancestor) {
+                return Some(next);
+            }
+            ancestor = self.parent(ancestor)?;
+        }
+        None
+    }
     pub fn prev_visible(&self, index: JsonMetadataIndex) -> Option<JsonMetadataIndex> {
-        let object = self[index];
-        if let Some(prev) = object.prev {
+        if let Some(prev) = self.prev_shown_sibling(index) {
             Some(self.last_in_childrens(prev))
         } else {
-            object.parent
+            self.parent(index)
         }
     }
     fn last_in_childrens(&self, index: JsonMetadataIndex) -> JsonMetadataIndex {
-        let object = self[index];
-        match object.ty {
-            ObjectType::Array | ObjectType::Structure if object.expanded => {
-                let mut last = JsonMetadataIndex::new(index.get() + 1);
-
-                while let Some(next) = self[last].next {
-                    last = next;
-                }
-                self.last_in_childrens(last)
+        if self.is_expanded(index)
+            && let Some(mut last) = self.first_shown_child(index)
+        {
+            while let Some(next) = self.next_shown_sibling(last) {
+                last = next;
             }
-            _ => index,
-        }
-    }
-    pub fn depth(&self, index: JsonMetadataIndex) -> usize {
-        if let Some(parent) = self[index].parent {
-            self.depth(parent) + 1
+            self.last_in_childrens(last)
         } else {
-            0
+            index
         }
     }
-}
-impl Index<JsonMetadataIndex> for JsonMetadata {
-    type Output = ObjectMeta;
-    fn index(&self, index: JsonMetadataIndex) -> &Self::Output {
-        &self.list[index.get()]
+    pub fn last_visible(&self) -> JsonMetadataIndex {
+        self.last_in_childrens(JsonMetadataIndex::ROOT)
     }
-}
-impl IndexMut<JsonMetadataIndex> for JsonMetadata {
-    fn index_mut(&mut self, index: JsonMetadataIndex) -> &mut Self::Output {
-        &mut self.list[index.get()]
+    /// Returns `index` or its collapsed or filtered ancestor, that hides it
+    pub fn nearest_visible(&self, mut index: JsonMetadataIndex) -> JsonMetadataIndex {
+        // ancestors of shown nodes are shown
+        while !self.is_shown(index)
+            && let Some(parent) = self.parent(index)
+        {
+            index = parent;
+        }
+        let mut visible = index;
+        let mut current = index;
+        while let Some(parent) = self.parent(current) {
+            if !self.is_expanded(parent) {
+                visible = parent;
+            }
+            current = parent;
+        }
+        visible
     }
-}
-impl Index<usize> for JsonMetadata {
-    type Output = ObjectMeta;
-    fn index(&self, index: usize) -> &Self::Output {
-        &self.list[index]
+    /// Collapses everything except root
+    pub fn collapse_all(&mut self) {
+        self.expanded.fill(false);
+        if !self.is_empty() {
+            self.expanded[JsonMetadataIndex::ROOT.get()] = true;
+        }
     }
-}
-impl IndexMut<usize> for JsonMetadata {
-    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
-        &mut self.list[index]
+    /// Nothing is expanded if document has invalid json
+    pub fn expand_all(&mut self, content: &Document) -> anyhow::Result<()> {
+        self.index_all(content)?;
+        self.expanded.fill(true);
+        Ok(())
     }
-}
-
-#[derive(Debug, Default)]
-enum ParsingState {
-    InStructWithName {
-        start: usize,
-        len: NonZeroU32,
-    },
-    InStructWithoutName,
-    InArray {
-        index: u64,
-    },
-    #[default]
-    TopLevel,
-}
-
-#[derive(Default)]
-pub struct ParsingContext {
-    output: JsonMetadata,
-    cursor: usize,
-    state: ParsingState,
-    parent: Option<JsonMetadataIndex>,
-    prev: Option<JsonMetadataIndex>,
-}
-
-pub fn parse_json_structure(content: &'static [u8]) -> JsonMetadata {
-    let mut ctx = ParsingContext::default();
-    loop {
-        if ctx.cursor == content.len() {
-            return ctx.output;
+    /// Expands containers above `depth` and collapses containers at `depth`.
+    /// Containers with invalid json stay collapsed, first error is returned.
+    pub fn expand_to_depth(&mut self, content: &Document, depth: usize) -> anyhow::Result<()> {
+        if self.is_empty() {
+            return Ok(());
         }
-        match content[ctx.cursor] {
-            b'[' => {
-                let new_index = ctx.add_object_meta(ObjectType::EmptyArray, 0);
-                ctx.parent = Some(new_index);
-                ctx.prev = None;
-                ctx.state = ParsingState::InArray { index: 0 };
-
-                ctx.cursor += 1;
-            }
-            b'{' => {
-                let new_index = ctx.add_object_meta(ObjectType::EmptyStructure, 0);
-                ctx.parent = Some(new_index);
-                ctx.prev = None;
-                ctx.state = ParsingState::InStructWithoutName;
-
-                ctx.cursor += 1;
-            }
-            b']' => {
-                ctx.cursor += 1;
-
-                let parent_ref_mut = &mut ctx.output[ctx.parent.unwrap()];
-                parent_ref_mut.source_len = ctx.cursor - parent_ref_mut.source_start;
-                match parent_ref_mut.name_or_index {
-                    NameOrIndex::Name { .. } => ctx.state = ParsingState::InStructWithoutName,
-                    NameOrIndex::Index(index) => {
-                        ctx.state = ParsingState::InArray { index: index + 1 }
-                    }
-                }
-                ctx.prev = ctx.parent;
-                ctx.parent = parent_ref_mut.parent;
-
-                if content.get(ctx.cursor) == Some(&b',') {
-                    ctx.cursor += 1;
-                }
-            }
-            b'}' => {
-                ctx.cursor += 1;
-
-                let parent_ref_mut = &mut ctx.output[ctx.parent.unwrap()];
-                parent_ref_mut.source_len = ctx.cursor - parent_ref_mut.source_start;
-                match parent_ref_mut.name_or_index {
-                    NameOrIndex::Name { .. } => ctx.state = ParsingState::InStructWithoutName,
-                    NameOrIndex::Index(index) => {
-                        ctx.state = ParsingState::InArray { index: index + 1 }
-                    }
-                }
-                ctx.prev = ctx.parent;
-                ctx.parent = parent_ref_mut.parent;
-
-                if content.get(ctx.cursor) == Some(&b',') {
-                    ctx.cursor += 1;
-                }
-            }
-            b'"' => {
-                let len = find_escaped_string_length(unsafe {
-                    str::from_utf8_unchecked(&content[ctx.cursor..])
-                })
-                .unwrap();
-
-                if let ParsingState::InStructWithoutName = ctx.state {
-                    ctx.state = ParsingState::InStructWithName {
-                        start: ctx.cursor,
-                        len: NonZeroU32::new(u32::try_from(len).unwrap()).unwrap(),
-                    };
-                    ctx.cursor += len;
-
-                    assert_eq!(content[ctx.cursor], b':');
-                    ctx.cursor += 1;
-                } else {
-                    let _ = ctx.add_object_meta(ObjectType::String, len);
-                    ctx.cursor += len;
-
-                    if content[ctx.cursor] == b',' {
-                        ctx.cursor += 1;
-                    }
-                }
-            }
-            b'-' | b'0'..=b'9' => {
-                let mut length = 1;
-                while content[ctx.cursor + length].is_ascii_digit() {
-                    length += 1;
-                }
-                if content[ctx.cursor + length] == b'.' {
-                    length += 1;
-                    while content[ctx.cursor + length].is_ascii_digit() {
-                        length += 1;
-                    }
-                }
-
-                let _ = ctx.add_object_meta(ObjectType::Number, length);
-
-                ctx.cursor += length;
-                if content[ctx.cursor] == b',' {
-                    ctx.cursor += 1;
-                }
-            }
-            b'n' => {
-                assert_eq!(&content[ctx.cursor..ctx.cursor + 4], b"null");
-
-                let _ = ctx.add_object_meta(ObjectType::Null, 4);
-
-                ctx.cursor += 4;
-                if content[ctx.cursor] == b',' {
-                    ctx.cursor += 1;
-                }
-            }
-            b't' => {
-                assert_eq!(&content[ctx.cursor..ctx.cursor + 4], b"true");
-
-                let _ = ctx.add_object_meta(ObjectType::Bool, 4);
-
-                ctx.cursor += 4;
-                if content[ctx.cursor] == b',' {
-                    ctx.cursor += 1;
-                }
-            }
-            b'f' => {
-                assert_eq!(&content[ctx.cursor..ctx.cursor + 5], b"false");
-
-                let _ = ctx.add_object_meta(ObjectType::Bool, 5);
-
-                ctx.cursor += 5;
-                if content[ctx.cursor] == b',' {
-                    ctx.cursor += 1;
-                }
-            }
-            c => {
-                todo!(
-                    "unknown character {:?} in symbols {:?} with cursor position '{}'",
-                    c as char,
-                    // str::from_utf8(
-                    //     &content[ctx.output[ctx.parent.unwrap()].source_start..=ctx.cursor + 10]
-                    // )
-                    // .unwrap()
-                    "",
-                    ctx.cursor,
-                )
+        let mut result = Ok(());
+        let mut level = vec![JsonMetadataIndex::ROOT];
+        for _ in 0..depth {
+            let mut next_level = Vec::new();
+            for index in level {
+                let expanded = self.set_expanded(content, index, true);
+                result = result.and(expanded);
+                next_level.extend(self.children(index));
             }
+            level = next_level;
         }
-        while content
-            .get(ctx.cursor)
-            .is_some_and(|c| c.is_ascii_whitespace())
-        {
-            ctx.cursor += 1;
+        for index in level {
+            self.collapse(index);
         }
+        result
     }
-}
-
-impl ParsingContext {
-    fn add_object_meta(&mut self, ty: ObjectType, source_len: usize) -> JsonMetadataIndex {
-        let meta = self.create_object_meta(ty, source_len);
-        self.push_object_meta(meta)
-    }
-
-    fn push_object_meta(&mut self, meta: ObjectMeta) -> JsonMetadataIndex {
-        let new_index = self.output.push(meta);
-        if let Some(prev) = self.prev {
-            assert_ne!(prev, new_index);
-            self.output[prev].next = Some(new_index);
+    pub fn expand_ancestors(&mut self, index: JsonMetadataIndex) {
+        let mut current = self.parent(index);
+        while let Some(parent) = current {
+            self.expanded[parent.get()] = true;
+            current = self.parent(parent);
         }
-        self.prev = Some(new_index);
-        new_index
     }
-
-    fn create_object_meta(&mut self, ty: ObjectType, source_len: usize) -> ObjectMeta {
-        match &mut self.state {
-            ParsingState::InStructWithName { start, len } => {
-                let parent = &mut self.output[self.parent.unwrap()].ty;
-                assert!(*parent == ObjectType::EmptyStructure || *parent == ObjectType::Structure);
-                *parent = ObjectType::Structure;
-
-                let name_or_index = NameOrIndex::Name {
-                    start: *start,
-                    len: *len,
-                };
-                let meta = ObjectMeta {
-                    name_or_index,
-                    ty,
-                    source_start: self.cursor,
-                    source_len,
-                    parent: self.parent,
-                    prev: self.prev,
-                    next: None,
-                    expanded: false,
-                };
-                self.state = ParsingState::InStructWithoutName;
-                meta
-            }
-            ParsingState::InStructWithoutName => todo!(),
-            ParsingState::InArray { index } => {
-                let parent = &mut self.output[self.parent.unwrap()].ty;
-                assert!(*parent == ObjectType::EmptyArray || *parent == ObjectType::Array);
-                *parent = ObjectType::Array;
-
-                let name_or_index = NameOrIndex::Index(*index);
-                *index += 1;
-                ObjectMeta {
-                    name_or_index,
-                    ty,
-                    source_start: self.cursor,
-                    source_len,
-                    parent: self.parent,
-                    prev: self.prev,
-                    next: None,
-                    expanded: false,
-                }
-            }
-            ParsingState::TopLevel => {
-                // FIXME
-                let name_or_index = NameOrIndex::Index(0);
-                ObjectMeta {
-                    name_or_index,
-                    ty,
-                    source_start: self.cursor,
-                    source_len,
-                    parent: self.parent,
-                    prev: self.prev,
-                    next: None,
-                    expanded: true,
-                }
-            }
+    pub fn depth(&self, index: JsonMetadataIndex) -> usize {
+        if let Some(parent) = self.parent(index) {
+            self.depth(parent) + 1
+        } else {
+            0
         }
     }
 }
 
+/// Indexes whole document, see `parse_json_structure_lazy` for huge documents
+pub fn parse_json_structure(content: &[u8]) -> anyhow::Result<JsonMetadata> {
+    let mut structure = index_document(content, true)?;
+    structure.shrink_to_fit();
+    Ok(structure)
+}
+
 /// Gets escaped string with opening quote and returns bytes count to closing quote(including it)
 /// Returns None if closing quote not found
 fn find_escaped_string_length(input: &str) -> Option<usize> {
@@ -399,3 +524,27 @@ fn find_escaped_string_length(input: &str) -> Option<usize> {
     }
     None
 }
+
+/// Bytes count of number, that starts at `start`: sign, digits, fraction and exponent
+fn number_length(content: &[u8], start: usize) -> usize {
+    let digits = |position: usize| {
+        content[position.min(content.len())..]
+            .iter()
+            .take_while(|ch| ch.is_ascii_digit())
+            .count()
+    };
+    let mut length = 1;
+    length += digits(start + length);
+    if content.get(start + length) == Some(&b'.') {
+        length += 1;
+        length += digits(start + length);
+    }
+    if matches!(content.get(start + length), Some(b'e' | b'E')) {
+        length += 1;
+        if matches!(content.get(start + length), Some(b'+' | b'-')) {
+            length += 1;
+        }
+        length += digits(start + length);
+    }
+    length
+}
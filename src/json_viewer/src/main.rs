@@ -1,10 +1,18 @@
 use std::{
-    fs::File,
-    io::{Read, Write},
+    borrow::Cow,
+    io::{BufWriter, Write},
+    path::PathBuf,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
     time::Instant,
 };
 
 use json_viewer::*;
+use lib_cli::ParsingRouter;
+
+mod cli;
 
 macro_rules! measured {
     ($name:literal, $code:expr) => {{
@@ -17,363 +25,201 @@ macro_rules! measured {
 }
 
 fn main() -> anyhow::Result<()> {
-    let args = std::env::args().collect::<Vec<_>>();
-    assert!(args.len() == 2);
-    let content: &'static [u8] = measured!("reading file", {
-        std::fs::read_to_string(&args[1])?.into_bytes().leak()
+    let cx = lib_cli::ParsingContext::from_args(lib_cli::Documentation {
+        names: lib_cli::Names::only_main("json_viewer"),
+        description: "interactive viewer for huge json files",
     });
-    let mut structure = measured!("parsing structure", {
-        // use std::hint::black_box;
-        // for _ in 1..20 {
-        //     parse_json_structure(black_box(content));
-        // }
-        parse_json_structure(content)
+    let mut result = Ok(());
+    cx.wrapper(|lib_cli::utils::AppPath(_path), lib_cli::TailArgs(args)| {
+        args.current_command(
+            |cli::PrintOnExit(print_on_exit),
+             cli::Lenient(lenient),
+             cli::YankFile(yank_file),
+             cli::SessionFile(session_file),
+             cli::ThemeOpt(theme),
+             cli::InputFile(path),
+             cli::OtherFile(other_path),
+             lib_cli::EmptyTail| {
+                let theme = Theme::from_env(theme);
+                result = match other_path {
+                    Some(other_path) => run_diff(path, other_path, lenient, theme),
+                    None => run(path, yank_file, session_file, print_on_exit, lenient, theme),
+                };
+            },
+        );
     });
-    let _alternate_screen_wrapper =
-        alternate_screen_wrapper::unix::AlternateScreenOnStdout::enter()?.unwrap();
-
-    let mut stdout = std::io::stdout();
-
-    // hide cursor
-    stdout.write_all(b"\x1B[?25l")?;
-    // disable line wrap
-    stdout.write_all(b"\x1B[?7l")?;
-
-    let mut scroll = JsonMetadataIndex::ROOT;
-    let mut selection = JsonMetadataIndex::ROOT;
-    let mut selection_relative = 0;
-
-    let mut stdin = std::io::stdin();
-    let mut buf = [0; 1024];
-    loop {
-        let height = render_frame(content, &structure, &mut stdout, scroll, selection)?;
-        stdout.flush()?;
+    result
+}
 
-        let count = stdin.read(&mut buf)?;
-        if count == 0 {
-            unreachable!("it's okay to delete this line, just checking");
-            // std::thread::sleep_ms(100);
-            // continue;
-        }
-        if buf[..count].contains(&b'q') {
-            break;
-        }
-        for &key in &buf[..count] {
-            match key {
-                b'j' => {
-                    let Some(index) = structure.next_visible(selection) else {
-                        continue;
-                    };
-                    selection_relative += 1;
-                    selection = index;
-                }
-                b'k' => {
-                    let Some(index) = structure.prev_visible(selection) else {
-                        continue;
-                    };
-                    selection_relative -= 1;
-                    selection = index;
-                }
-                b'\r' => {
-                    let object = &mut structure[selection];
-                    object.expanded = !object.expanded;
-                }
-                _ => {}
-            }
-            while selection_relative >= height as i32 {
-                scroll = structure.next_visible(scroll).unwrap();
-                selection_relative -= 1;
-            }
-            while selection_relative < 0 {
-                scroll = structure.prev_visible(scroll).unwrap();
-                selection_relative += 1;
-            }
+fn run_diff(
+    left_path: Option<PathBuf>,
+    right_path: PathBuf,
+    lenient: bool,
+    theme: Theme,
+) -> anyhow::Result<()> {
+    let mut left_content: Cow<'static, [u8]> = measured!("reading files", {
+        match &left_path {
+            Some(path) => read_input(path)?,
+            None => read_stdin()?,
         }
+    })
+    .into();
+    let mut right_content: Cow<'static, [u8]> = read_input(&right_path)?.into();
+    if lenient {
+        measured!("normalizing", {
+            left_content = normalize_lenient(&left_content)?.into();
+            right_content = normalize_lenient(&right_content)?.into();
+        });
     }
-
+    ensure_not_empty(&left_content)?;
+    ensure_not_empty(&right_content)?;
+    measured!("validating utf-8", {
+        str::from_utf8(&left_content)?;
+        str::from_utf8(&right_content)?;
+    });
+    let (left, right) = measured!("parsing structure", {
+        (
+            parse_json_structure(&left_content)?,
+            parse_json_structure(&right_content)?,
+        )
+    });
+    let (left_content, right_content) = (Document::new(left_content), Document::new(right_content));
+    let diff = measured!("diffing", {
+        diff_structures(&left_content, &left, &right_content, &right)
+    });
+    let terminal = Terminal::enter()?;
+    let terminal_size = rustix::termios::tcgetwinsize(terminal.tty())?;
+    let mut screen = Screen::new(terminal_size.ws_col, terminal_size.ws_row);
+    let mut viewer = DiffViewer::new(
+        Side {
+            content: left_content,
+            structure: left,
+        },
+        Side {
+            content: right_content,
+            structure: right,
+        },
+        diff,
+        screen.width,
+        screen.height,
+        theme,
+    );
+    run_ui(&terminal, &mut screen, &mut viewer)?;
     Ok(())
 }
 
-/// # Returns terminal height
-fn render_frame(
-    content: &'static [u8],
-    structure: &JsonMetadata,
-    stdout: &mut std::io::Stdout,
-    scroll: JsonMetadataIndex,
-    selection: JsonMetadataIndex,
-) -> anyhow::Result<u16> {
-    // clear screen
-    stdout.write_all(b"\x1B[2J")?;
-
-    let rustix::termios::Winsize {
-        ws_row: height,
-        ws_col: width,
-        ..
-    } = rustix::termios::tcgetwinsize(File::open("/dev/tty")?)?;
-
-    let overview_lines = render_overview(content, structure, scroll, height as usize, selection);
-    let data_lines = if structure[selection].source_len < 10000 {
-        Some(render_data(content, structure, selection))
-    } else {
-        None
-    };
-    for i in 0..height as usize {
-        let height_gap = 0;
-        let mut width_gap = 0;
-        if let Some(line) = overview_lines.get(i) {
-            // move cursor
-            stdout.write_fmt(format_args!(
-                "\x1B[{};{}H",
-                i as u16 + height_gap + 1, // height
-                width_gap + 1,             // width
-            ))?;
-            stdout.write_all(line.as_bytes())?;
-        }
-        width_gap = (width * 2 / 5) as usize;
-        stdout.write_all(&b" ".repeat(width_gap))?;
-        // move cursor
-        stdout.write_fmt(format_args!(
-            "\x1B[{};{}H",
-            i as u16 + height_gap + 1, // height
-            width_gap + 1,             // width
-        ))?;
-        stdout.write_all(RESET.as_bytes())?;
-        // move cursor
-        stdout.write_fmt(format_args!(
-            "\x1B[{};{}H",
-            i as u16 + height_gap + 1, // height
-            width_gap + 1,             // width
-        ))?;
-        stdout.write_all(b"\x1b[K | ")?;
-        if let Some(data_lines) = &data_lines
-            && let Some(line) = data_lines.get(i)
-        {
-            stdout.write_all(line.as_bytes())?;
+fn run(
+    path: Option<PathBuf>,
+    yank_file: Option<PathBuf>,
+    session_file: Option<PathBuf>,
+    print_on_exit: bool,
+    lenient: bool,
+    theme: Theme,
+) -> anyhow::Result<()> {
+    let mut content: Cow<'static, [u8]> = measured!("reading file", {
+        match &path {
+            Some(path) => read_input(path)?,
+            None => read_stdin()?,
         }
+    })
+    .into();
+    if lenient {
+        content = measured!("normalizing", { normalize_lenient(&content)?.into() });
     }
-    Ok(height)
-}
+    ensure_not_empty(&content)?;
+    measured!("validating utf-8", { str::from_utf8(&content)? });
+    let structure = measured!("parsing structure", {
+        parse_json_structure_lazy(&content)?
+    });
+    // stdin could be the document and stdout is used by `--print-on-exit`,
+    // so keys are read from terminal directly and frames are written to it
+    let terminal = Terminal::enter()?;
+    let terminal_size = rustix::termios::tcgetwinsize(terminal.tty())?;
+    let mut screen = Screen::new(terminal_size.ws_col, terminal_size.ws_row);
+    let mut viewer = Viewer::new(
+        Document::new(content),
+        structure,
+        screen.width,
+        screen.height,
+        ViewerOptions {
+            path: path.clone(),
+            yank_file,
+            lenient,
+            theme,
+        },
+    );
 
-const ITALIC: &str = "\x1b[3m";
-const RED_FG: &str = "\x1b[31m";
-const GREEN_FG: &str = "\x1b[32m";
-const YELLOW_FG: &str = "\x1b[33m";
-const BLUE_FG: &str = "\x1b[34m";
-const CYAN_FG: &str = "\x1b[36m";
-const BLUE_BG: &str = "\x1b[44m";
-// const MAGENTA_BG: &str = "\x1b[45m";
-const RESET: &str = "\x1b[0m";
-fn render_overview(
-    content: &'static [u8],
-    structure: &JsonMetadata,
-    start: JsonMetadataIndex,
-    lines_needed: usize,
-    selection: JsonMetadataIndex,
-) -> Vec<String> {
-    let mut lines = Vec::new();
-    let mut current_ix = start;
-    let mut indentation = structure.depth(start);
-    'outer: while lines.len() < lines_needed {
-        let mut current = structure[current_ix];
-        let prefix = match current.name_or_index {
-            NameOrIndex::Name { start, len } => {
-                str::from_utf8(&content[(start + 1)..(start + len.get() as usize - 1)])
-                    .unwrap()
-                    .to_owned()
-            }
-            NameOrIndex::Index(index) => format!("{index}"),
-        };
-        let current_line_selected = current_ix == selection;
-        let red_fg = if current_line_selected { "" } else { RED_FG };
-        let green_fg = if current_line_selected { "" } else { GREEN_FG };
-        let blue_fg = if current_line_selected { "" } else { BLUE_FG };
-        let cyan_fg = if current_line_selected { "" } else { CYAN_FG };
-        let bool_style = if current_line_selected {
-            String::new()
-        } else {
-            ITALIC.to_owned() + RED_FG
-        };
-        let mut prefix = format!(
-            "{selection}{indentation}{prefix} ",
-            selection = if current_line_selected {
-                BLUE_BG
-            } else {
-                RESET
-            },
-            indentation = "  ".repeat(indentation)
-        );
-        match current.ty {
-            ObjectType::Array => {
-                if indentation > 0 {
-                    unsafe {
-                        prefix.as_bytes_mut()
-                            [(indentation + 1) * 2 + current_line_selected as usize] =
-                            if current.expanded { b'-' } else { b'+' };
-                    }
-                }
-                lines.push(format!("{prefix}{cyan_fg}{ITALIC}arr"));
-                if current.expanded {
-                    current_ix.0 += 1;
-                    indentation += 1;
-                    continue;
-                }
-            }
-            ObjectType::Structure => {
-                if indentation > 0 {
-                    unsafe {
-                        prefix.as_bytes_mut()
-                            [(indentation + 1) * 2 + current_line_selected as usize] =
-                            if current.expanded { b'-' } else { b'+' };
-                    }
-                }
-                lines.push(format!("{prefix}{cyan_fg}{ITALIC}obj"));
-                if current.expanded {
-                    current_ix.0 += 1;
-                    indentation += 1;
-                    continue;
-                }
-            }
-            ObjectType::EmptyArray => {
-                lines.push(format!("{prefix}= []"));
-            }
-            ObjectType::EmptyStructure => {
-                lines.push(format!("{prefix}= {{}}"));
-            }
-            ObjectType::String => {
-                lines.push(format!(
-                    "{prefix}{green_fg}{}",
-                    str::from_utf8(
-                        &content[current.source_start..current.source_start + current.source_len]
-                    )
-                    .unwrap()
-                ));
-            }
-            ObjectType::Null => {
-                lines.push(format!("{prefix}{blue_fg}null"));
-            }
-            ObjectType::Number => {
-                lines.push(format!(
-                    "{prefix}{red_fg}{}",
-                    str::from_utf8(
-                        &content[current.source_start..current.source_start + current.source_len]
-                    )
-                    .unwrap()
-                ));
-            }
-            ObjectType::Bool => {
-                lines.push(format!(
-                    "{prefix}{bool_style}{}",
-                    str::from_utf8(
-                        &content[current.source_start..current.source_start + current.source_len]
-                    )
-                    .unwrap()
+    // session is kept only for files
+    let session_key = match (&session_file, &path) {
+        (Some(session_file), Some(path)) => Some((session_file.as_path(), path.as_path())),
+        _ => None,
+    };
+    if let Some((session_file, document)) = session_key {
+        match load_session(session_file, document, std::fs::metadata(document)?.len()) {
+            Ok(Some(session)) => viewer.restore_session(&session),
+            Ok(None) => {}
+            Err(err) => {
+                viewer.set_message(format!(
+                    "can't read session {}: {err}",
+                    session_file.display()
                 ));
             }
         }
-        loop {
-            if let Some(next) = current.next {
-                current_ix = next;
-                break;
-            } else if let Some(parent) = current.parent {
-                let parent = structure[parent];
-                indentation -= 1;
-                current = parent;
-            } else {
-                break 'outer;
-            }
+    }
+    let exit = run_ui(&terminal, &mut screen, &mut viewer)?;
+    drop(terminal);
+    if let Some((session_file, document)) = session_key {
+        // size changes if edits were saved
+        let saved = std::fs::metadata(document).and_then(|metadata| {
+            save_session(session_file, document, metadata.len(), &viewer.session())
+        });
+        if let Err(err) = saved {
+            eprintln!("can't save session {}: {err}", session_file.display());
         }
     }
-    lines
+    if print_on_exit && exit == Exit::Quit {
+        let mut stdout = BufWriter::new(std::io::stdout().lock());
+        viewer.write_focused(&mut stdout)?;
+        stdout.flush()?;
+    }
+    Ok(())
 }
 
-fn render_data(
-    content: &'static [u8],
-    structure: &JsonMetadata,
-    root_ix: JsonMetadataIndex,
-) -> Vec<String> {
-    let mut lines = Vec::new();
-    let mut current_ix = root_ix;
-    let mut indentation = 0;
-    'outer: loop {
-        let mut current = structure[current_ix];
-        let prefix = if current_ix == root_ix {
-            String::new()
-        } else if let NameOrIndex::Name { start, len } = current.name_or_index {
-            format!(
-                "{YELLOW_FG}{ITALIC}{}{RESET}: ",
-                str::from_utf8(&content[start..start + len.get() as usize])
-                    .unwrap()
-                    .to_owned()
-            )
-        } else {
-            String::new()
-        };
-        let prefix = format!(
-            "{indentation}{prefix}",
-            indentation = "  ".repeat(indentation)
-        );
-        let styles = match current.ty {
-            ObjectType::Array => {
-                lines.push(format!("{prefix}["));
-                current_ix.0 += 1;
-                indentation += 1;
-                continue;
-            }
-            ObjectType::Structure => {
-                lines.push(format!("{prefix}{{"));
-                current_ix.0 += 1;
-                indentation += 1;
-                continue;
-            }
-            ObjectType::EmptyArray => "",
-            ObjectType::EmptyStructure => "",
-            ObjectType::String => GREEN_FG,
-            ObjectType::Bool => &(ITALIC.to_owned() + RED_FG),
-            ObjectType::Number => RED_FG,
-            ObjectType::Null => BLUE_FG,
+/// Draws frames of `view` and passes keys to it, until it requests exit
+fn run_ui(
+    terminal: &Terminal,
+    screen: &mut Screen,
+    view: &mut impl Interactive,
+) -> anyhow::Result<Exit> {
+    let mut output = terminal.tty();
+    let mut buf = [0; 1024];
+    let resized = Arc::new(AtomicBool::new(false));
+    signal_hook::flag::register(signal_hook::consts::SIGWINCH, Arc::clone(&resized))?;
+    loop {
+        if resized.swap(false, Ordering::Relaxed) {
+            let terminal_size = rustix::termios::tcgetwinsize(terminal.tty())?;
+            view.resize(terminal_size.ws_col, terminal_size.ws_row);
+            screen.resize(terminal_size.ws_col, terminal_size.ws_row);
+        }
+        screen.draw(&mut output, view.render())?;
+
+        let Some(input) = terminal.read_input(&mut buf)? else {
+            continue;
         };
-        lines.push(format!(
-            "{prefix}{styles}{}{RESET}{}",
-            {
-                match current.ty {
-                    ObjectType::EmptyArray => "[]",
-                    ObjectType::EmptyStructure => "{}",
-                    _ => str::from_utf8(
-                        &content[current.source_start..current.source_start + current.source_len],
-                    )
-                    .unwrap(),
-                }
-            },
-            if current.next.is_some() && current_ix != root_ix {
-                ","
-            } else {
-                ""
-            }
-        ));
-        loop {
-            if current_ix == root_ix {
-                break 'outer;
+        // terminal is closed
+        if input.is_empty() {
+            return Ok(Exit::QuitWithoutPrinting);
+        }
+        for key in decode_keys(input) {
+            let exit = view.handle_key(key);
+            let terminal_output = view.take_terminal_output();
+            if !terminal_output.is_empty() {
+                output.write_all(&terminal_output)?;
+                output.flush()?;
             }
-            let parent_ix = current.parent.unwrap();
-            if let Some(next) = current.next {
-                current_ix = next;
-                break;
-            } else {
-                let parent = structure[parent_ix];
-                indentation -= 1;
-                let closing = match parent.ty {
-                    ObjectType::Array => "]",
-                    ObjectType::Structure => "}",
-                    _ => unreachable!(),
-                };
-                lines.push(format!(
-                    "{indentation}{closing}",
-                    indentation = "  ".repeat(indentation)
-                ));
-                current_ix = parent_ix;
-                current = parent;
+            if let Some(exit) = exit {
+                return Ok(exit);
             }
         }
     }
-    lines
 }
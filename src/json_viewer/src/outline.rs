@@ -0,0 +1,160 @@
+use crate::*;
+
+/// Tree, that is shown as rows of visible nodes, like overview and diff
+pub(crate) trait Outline {
+    type Index: Copy + Eq;
+
+    fn next_visible(&self, index: Self::Index) -> Option<Self::Index>;
+    fn prev_visible(&self, index: Self::Index) -> Option<Self::Index>;
+    fn displayed_before(&self, a: Self::Index, b: Self::Index) -> bool;
+    fn depth(&self, index: Self::Index) -> usize;
+    /// Has `+`/`-` marker before name, that toggles expansion on click
+    fn has_marker(&self, index: Self::Index) -> bool;
+}
+
+impl Outline for JsonMetadata {
+    type Index = JsonMetadataIndex;
+
+    fn next_visible(&self, index: JsonMetadataIndex) -> Option<JsonMetadataIndex> {
+        JsonMetadata::next_visible(self, index)
+    }
+    fn prev_visible(&self, index: JsonMetadataIndex) -> Option<JsonMetadataIndex> {
+        JsonMetadata::prev_visible(self, index)
+    }
+    fn displayed_before(&self, a: JsonMetadataIndex, b: JsonMetadataIndex) -> bool {
+        JsonMetadata::displayed_before(self, a, b)
+    }
+    fn depth(&self, index: JsonMetadataIndex) -> usize {
+        JsonMetadata::depth(self, index)
+    }
+    fn has_marker(&self, index: JsonMetadataIndex) -> bool {
+        matches!(self.ty(index), ObjectType::Array | ObjectType::Structure)
+    }
+}
+
+impl Outline for DiffTree {
+    type Index = DiffIndex;
+
+    fn next_visible(&self, index: DiffIndex) -> Option<DiffIndex> {
+        DiffTree::next_visible(self, index)
+    }
+    fn prev_visible(&self, index: DiffIndex) -> Option<DiffIndex> {
+        DiffTree::prev_visible(self, index)
+    }
+    // visible nodes are in index order
+    fn displayed_before(&self, a: DiffIndex, b: DiffIndex) -> bool {
+        a < b
+    }
+    fn depth(&self, index: DiffIndex) -> usize {
+        DiffTree::depth(self, index)
+    }
+    fn has_marker(&self, index: DiffIndex) -> bool {
+        self.get(index).first_child.is_some()
+    }
+}
+
+/// # Returns up to `count` rows, that start at `top`
+pub(crate) fn outline_rows<T: Outline>(tree: &T, top: T::Index, count: u16) -> Vec<T::Index> {
+    std::iter::successors(Some(top), |&index| tree.next_visible(index))
+        .take(count as usize)
+        .collect()
+}
+
+/// # Returns scroll position, that puts selection in the middle of screen
+pub(crate) fn center_on<T: Outline>(tree: &T, selection: T::Index, height: u16) -> T::Index {
+    let mut scroll = selection;
+    for _ in 0..height / 2 {
+        let Some(prev) = tree.prev_visible(scroll) else {
+            break;
+        };
+        scroll = prev;
+    }
+    scroll
+}
+
+/// # Returns scroll position closest to `scroll`, that shows selection
+pub(crate) fn scroll_to_selection<T: Outline>(
+    tree: &T,
+    scroll: T::Index,
+    selection: T::Index,
+    height: u16,
+) -> T::Index {
+    if tree.displayed_before(selection, scroll) || selection == scroll {
+        return selection;
+    }
+    let mut current = scroll;
+    for _ in 1..height {
+        match tree.next_visible(current) {
+            Some(next) if next == selection => return scroll,
+            Some(next) => current = next,
+            None => break,
+        }
+    }
+    let mut scroll = selection;
+    for _ in 1..height {
+        let Some(prev) = tree.prev_visible(scroll) else {
+            break;
+        };
+        scroll = prev;
+    }
+    scroll
+}
+
+/// Lines scrolled by one step of mouse wheel
+pub(crate) const WHEEL_LINES: usize = 3;
+
+/// What click on row of outline does
+pub(crate) enum Click<I> {
+    Select(I),
+    /// Click on `+`/`-` marker before name
+    Toggle(I),
+}
+
+/// `column` is counted from left edge of outline
+pub(crate) fn click<T: Outline>(
+    tree: &T,
+    scroll: T::Index,
+    height: u16,
+    row: u16,
+    column: u16,
+) -> Option<Click<T::Index>> {
+    let &index = outline_rows(tree, scroll, height).get(row as usize)?;
+    // top level value has no marker
+    let depth = tree.depth(index);
+    let on_marker = depth > 0 && tree.has_marker(index) && column as usize == (depth - 1) * 2;
+    Some(match on_marker {
+        true => Click::Toggle(index),
+        false => Click::Select(index),
+    })
+}
+
+/// Scrolls by `WHEEL_LINES` rows, selection is moved, if it's scrolled out of screen
+pub(crate) fn scroll_wheel<T: Outline>(
+    tree: &T,
+    scroll: &mut T::Index,
+    selection: &mut T::Index,
+    height: u16,
+    down: bool,
+) {
+    for _ in 0..WHEEL_LINES {
+        let next = if down {
+            // stops when last row is at the bottom
+            let rows = outline_rows(tree, *scroll, height + 1);
+            rows.get(1)
+                .copied()
+                .filter(|_| rows.len() > height as usize)
+        } else {
+            tree.prev_visible(*scroll)
+        };
+        let Some(next) = next else {
+            break;
+        };
+        *scroll = next;
+    }
+    let rows = outline_rows(tree, *scroll, height);
+    if !rows.contains(selection)
+        && let Some(&row) = if down { rows.first() } else { rows.last() }
+    {
+        *selection = row;
+    }
+}
@@ -0,0 +1,205 @@
+use anyhow::{Result, anyhow, bail};
+
+use crate::*;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PathSelector {
+    /// Raw(still escaped) key name
+    Name(String),
+    /// Negative index counts from the end
+    Index(i64),
+    Wildcard,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathSegment {
+    /// `..` - match at any depth
+    pub recursive: bool,
+    pub selector: PathSelector,
+}
+
+/// Parses jq/JSONPath like paths: `.items[3].metadata.name`, `$..id`, `.["key with spaces"]`, `.items[*]`
+pub fn parse_path(input: &str) -> Result<Vec<PathSegment>> {
+    let mut segments = Vec::new();
+    let input = input.trim();
+    let mut rest = input.strip_prefix('$').unwrap_or(input);
+    while !rest.is_empty() {
+        let recursive = if let Some(tail) = rest.strip_prefix("..") {
+            rest = tail;
+            true
+        } else if let Some(tail) = rest.strip_prefix('.') {
+            rest = tail;
+            false
+        } else if rest.starts_with('[') {
+            false
+        } else {
+            bail!("expected '.' or '[' at '{rest}'");
+        };
+
+        let selector = if let Some(tail) = rest.strip_prefix('[') {
+            let (selector, tail) = parse_bracket(tail)?;
+            rest = tail;
+            selector
+        } else if let Some(tail) = rest.strip_prefix('*') {
+            rest = tail;
+            PathSelector::Wildcard
+        } else {
+            let name_len = rest.find(['.', '[']).unwrap_or(rest.len());
+            let (name, tail) = rest.split_at(name_len);
+            rest = tail;
+            if name.is_empty() {
+                if recursive {
+                    bail!("expected key after '..'");
+                }
+                // lone dot(jq identity) or `.[`
+                continue;
+            }
+            PathSelector::Name(name.to_owned())
+        };
+        segments.push(PathSegment {
+            recursive,
+            selector,
+        });
+    }
+    Ok(segments)
+}
+
+/// Gets bracket content after opening bracket, returns selector and rest after closing bracket
+fn parse_bracket(input: &str) -> Result<(PathSelector, &str)> {
+    if let Some(quote) = input.chars().next().filter(|&ch| ch == '"' || ch == '\'') {
+        let mut last_backslash = false;
+        for (index, ch) in input.char_indices().skip(1) {
+            match ch {
+                '\\' if !last_backslash => last_backslash = true,
+                ch if ch == quote && !last_backslash => {
+                    let rest = input[index + 1..]
+                        .strip_prefix(']')
+                        .ok_or(anyhow!("expected ']' after quoted key"))?;
+                    return Ok((PathSelector::Name(input[1..index].to_owned()), rest));
+                }
+                _ => last_backslash = false,
+            }
+        }
+        bail!("unterminated quoted key");
+    }
+    let (inner, rest) = input
+        .split_once(']')
+        .ok_or(anyhow!("expected ']' after '[{input}'"))?;
+    let selector = match inner.trim() {
+        "*" => PathSelector::Wildcard,
+        index => PathSelector::Index(
+            index
+                .parse()
+                .map_err(|_| anyhow!("invalid array index '{index}'"))?,
+        ),
+    };
+    Ok((selector, rest))
+}
+
+/// Returns all nodes matching `path` in document order.
+/// Indexes containers on the way.
+pub fn resolve_path(
+    content: &Document,
+    structure: &mut JsonMetadata,
+    path: &str,
+) -> Result<Vec<JsonMetadataIndex>> {
+    resolve_relative_path(content, structure, JsonMetadataIndex::ROOT, path)
+}
+
+/// Like `resolve_path`, but `path` starts at `start` instead of root
+pub fn resolve_relative_path(
+    content: &Document,
+    structure: &mut JsonMetadata,
+    start: JsonMetadataIndex,
+    path: &str,
+) -> Result<Vec<JsonMetadataIndex>> {
+    let segments = parse_path(path)?;
+    let mut current = vec![start];
+    for segment in &segments {
+        let mut next = Vec::new();
+        for &index in &current {
+            if segment.recursive {
+                structure.index_subtree(content, index)?;
+                let candidates = std::iter::once(index).chain(structure.descendants(index));
+                for candidate in candidates {
+                    select_children(content, structure, candidate, &segment.selector, &mut next);
+                }
+            } else {
+                structure.index_children(content, index)?;
+                select_children(content, structure, index, &segment.selector, &mut next);
+            }
+        }
+        next.sort_unstable_by_key(|&index| structure.source_start(index));
+        next.dedup();
+        current = next;
+    }
+    Ok(current)
+}
+
+fn select_children(
+    content: &Document,
+    structure: &JsonMetadata,
+    index: JsonMetadataIndex,
+    selector: &PathSelector,
+    output: &mut Vec<JsonMetadataIndex>,
+) {
+    match selector {
+        PathSelector::Wildcard => output.extend(structure.children(index)),
+        PathSelector::Name(name) => output.extend(
+            structure
+                .children(index)
+                .filter(|&child| structure.get(child).name(content) == Some(name.as_bytes())),
+        ),
+        &PathSelector::Index(requested) => {
+            if structure.ty(index) != ObjectType::Array {
+                return;
+            }
+            let position = if requested < 0 {
+                let len = structure.children(index).count() as i64;
+                requested + len
+            } else {
+                requested
+            };
+            if let Ok(position) = usize::try_from(position)
+                && let Some(child) = structure.children(index).nth(position)
+            {
+                output.push(child);
+            }
+        }
+    }
+}
+
+/// Builds path to `index` in the same syntax, that `parse_path` accepts
+pub fn path_to(content: &Document, structure: &JsonMetadata, index: JsonMetadataIndex) -> String {
+    let mut segments = Vec::new();
+    let mut current = index;
+    while let Some(parent) = structure.parent(current) {
+        let object = structure.get(current);
+        segments.push(match object.name_or_index {
+            NameOrIndex::Name { .. } => {
+                let name = str::from_utf8(object.name(content).unwrap()).unwrap();
+                let is_identifier = name.chars().enumerate().all(|(position, ch)| {
+                    ch == '_' || ch.is_ascii_alphabetic() || (position > 0 && ch.is_ascii_digit())
+                });
+                if is_identifier && !name.is_empty() {
+                    format!(".{name}")
+                } else {
+                    format!("[\"{name}\"]")
+                }
+            }
+            NameOrIndex::Index(position) => format!("[{position}]"),
+            NameOrIndex::Root => unreachable!("root has no parent"),
+        });
+        current = parent;
+    }
+    if segments.is_empty() {
+        return ".".to_owned();
+    }
+    segments.reverse();
+    let path = segments.concat();
+    if path.starts_with('[') {
+        format!(".{path}")
+    } else {
+        path
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::*;
+
+const EXAMPLES_LIMIT: usize = 3;
+
+/// Statistics of all values found at one path, where array indices are replaced with `[*]`
+#[derive(Default)]
+struct SchemaNode<'a> {
+    count: usize,
+    types: BTreeMap<&'static str, usize>,
+    /// Raw(still escaped) key names in order of first appearance
+    properties: Vec<(&'a [u8], SchemaNode<'a>)>,
+    property_positions: HashMap<&'a [u8], usize>,
+    items: Option<Box<SchemaNode<'a>>>,
+    min_items: usize,
+    max_items: usize,
+    /// Distinct raw scalar values
+    examples: Vec<&'a [u8]>,
+}
+
+/// Infers JSON Schema of the whole document.
+/// Besides standard keywords, it has `x-count` with number of values at that path
+/// and `x-types` with number of values of each type, when there are several.
+/// Indexes whole document.
+pub fn infer_schema(content: &Document, structure: &mut JsonMetadata) -> anyhow::Result<String> {
+    structure.index_all(content)?;
+    let mut root = SchemaNode::default();
+    if !structure.is_empty() {
+        root.add(content, structure, JsonMetadataIndex::ROOT);
+    }
+    let mut keywords =
+        vec!["\"$schema\":\"https://json-schema.org/draft/2020-12/schema\"".to_owned()];
+    keywords.extend(root.keywords());
+    Ok(format!("{{{}}}", keywords.join(",")))
+}
+
+fn type_name(content: &Document, object: &ObjectMeta) -> &'static str {
+    match object.ty {
+        ObjectType::String => "string",
+        ObjectType::Bool => "boolean",
+        ObjectType::Null => "null",
+        ObjectType::Number => {
+            if object
+                .source(content)
+                .iter()
+                .any(|&ch| matches!(ch, b'.' | b'e' | b'E'))
+            {
+                "number"
+            } else {
+                "integer"
+            }
+        }
+        ObjectType::EmptyArray | ObjectType::Array => "array",
+        ObjectType::EmptyStructure | ObjectType::Structure => "object",
+    }
+}
+
+impl<'a> SchemaNode<'a> {
+    fn add(&mut self, content: &'a Document, structure: &JsonMetadata, index: JsonMetadataIndex) {
+        let object = structure.get(index);
+        let is_first_array = !self.types.contains_key("array");
+        self.count += 1;
+        *self.types.entry(type_name(content, &object)).or_default() += 1;
+        match object.ty {
+            ObjectType::Structure => {
+                for child in structure.children(index) {
+                    let name = structure.get(child).name(content).unwrap();
+                    let position = *self.property_positions.entry(name).or_insert_with(|| {
+                        self.properties.push((name, SchemaNode::default()));
+                        self.properties.len() - 1
+                    });
+                    self.properties[position].1.add(content, structure, child);
+                }
+            }
+            ObjectType::Array | ObjectType::EmptyArray => {
+                let items = self.items.get_or_insert_default();
+                let mut len = 0;
+                for child in structure.children(index) {
+                    items.add(content, structure, child);
+                    len += 1;
+                }
+                if is_first_array {
+                    self.min_items = len;
+                    self.max_items = len;
+                } else {
+                    self.min_items = self.min_items.min(len);
+                    self.max_items = self.max_items.max(len);
+                }
+            }
+            ObjectType::String | ObjectType::Bool | ObjectType::Number | ObjectType::Null => {
+                let source =
+                    content.token(object.source_start..object.source_start + object.source_len);
+                if self.examples.len() < EXAMPLES_LIMIT && !self.examples.contains(&source) {
+                    self.examples.push(source);
+                }
+            }
+            ObjectType::EmptyStructure => {}
+        }
+    }
+
+    fn keywords(&self) -> Vec<String> {
+        let mut keywords = Vec::new();
+        let types = self.types.keys().map(|ty| format!("\"{ty}\""));
+        keywords.push(if self.types.len() == 1 {
+            format!("\"type\":{}", list(types))
+        } else {
+            format!("\"type\":[{}]", list(types))
+        });
+        keywords.push(format!("\"x-count\":{}", self.count));
+        if self.types.len() > 1 {
+            let counts = self
+                .types
+                .iter()
+                .map(|(ty, count)| format!("\"{ty}\":{count}"));
+            keywords.push(format!("\"x-types\":{{{}}}", list(counts)));
+        }
+        if let Some(&objects) = self.types.get("object") {
+            let properties = self.properties.iter().map(|(name, property)| {
+                format!(
+                    "\"{}\":{}",
+                    str::from_utf8(name).unwrap(),
+                    property.schema()
+                )
+            });
+            keywords.push(format!("\"properties\":{{{}}}", list(properties)));
+            let required = self
+                .properties
+                .iter()
+                .filter(|(_, property)| property.count == objects)
+                .map(|(name, _)| format!("\"{}\"", str::from_utf8(name).unwrap()));
+            keywords.push(format!("\"required\":[{}]", list(required)));
+        }
+        if let Some(items) = &self.items {
+            keywords.push(format!("\"minItems\":{}", self.min_items));
+            keywords.push(format!("\"maxItems\":{}", self.max_items));
+            if items.count > 0 {
+                keywords.push(format!("\"items\":{}", items.schema()));
+            }
+        }
+        if !self.examples.is_empty() {
+            let examples = self
+                .examples
+                .iter()
+                .map(|example| str::from_utf8(example).unwrap().to_owned());
+            keywords.push(format!("\"examples\":[{}]", list(examples)));
+        }
+        keywords
+    }
+
+    fn schema(&self) -> String {
+        format!("{{{}}}", self.keywords().join(","))
+    }
+}
+
+fn list(items: impl Iterator<Item = String>) -> String {
+    items.collect::<Vec<_>>().join(",")
+}
@@ -0,0 +1,52 @@
+use std::io::Write;
+
+use crate::RESET;
+
+/// Last drawn frame, so only changed lines are written to terminal
+#[derive(Default)]
+pub struct Screen {
+    lines: Vec<String>,
+    pub width: u16,
+    pub height: u16,
+}
+impl Screen {
+    pub fn new(width: u16, height: u16) -> Self {
+        Screen {
+            lines: Vec::new(),
+            width,
+            height,
+        }
+    }
+    /// Forces full redraw on next `draw`
+    pub fn resize(&mut self, width: u16, height: u16) {
+        self.width = width;
+        self.height = height;
+        self.lines.clear();
+    }
+
+    /// Every line is drawn from the first column and may contain escape sequences.
+    /// Everything is written at once and wrapped in synchronized update, so terminal doesn't show partial frames.
+    pub fn draw(&mut self, output: &mut impl Write, lines: Vec<String>) -> std::io::Result<()> {
+        let mut buf = Vec::new();
+        // begin synchronized update
+        buf.extend_from_slice(b"\x1b[?2026h");
+        if lines.len() != self.lines.len() {
+            // clear screen
+            buf.extend_from_slice(b"\x1b[2J");
+            self.lines.clear();
+        }
+        for (i, line) in lines.iter().enumerate() {
+            if self.lines.get(i) == Some(line) {
+                continue;
+            }
+            // move cursor, reset style, so clearing doesn't use previous background, clear line
+            write!(buf, "\x1b[{};1H{RESET}\x1b[2K{line}", i + 1)?;
+        }
+        // end synchronized update
+        buf.extend_from_slice(b"\x1b[?2026l");
+        output.write_all(&buf)?;
+        output.flush()?;
+        self.lines = lines;
+        Ok(())
+    }
+}
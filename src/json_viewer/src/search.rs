@@ -0,0 +1,101 @@
+use crate::*;
+
+/// Returns all nodes, whose key name or scalar value contains `pattern`, in document order.
+/// Search is case insensitive if `pattern` has no uppercase letters.
+/// Only indexed nodes are searched, see `index_all`.
+pub fn find_matches(
+    content: &Document,
+    structure: &JsonMetadata,
+    pattern: &[u8],
+) -> Vec<JsonMetadataIndex> {
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+    let ignore_case = is_ignore_case(pattern);
+    let mut matches: Vec<_> = (0..structure.len())
+        .map(JsonMetadataIndex::new)
+        .filter(|&index| is_match(content, structure.get(index), pattern, ignore_case))
+        .collect();
+    matches.sort_unstable_by_key(|&index| structure.source_start(index));
+    matches
+}
+
+/// Keeps `matches` of a shorter pattern, that `pattern` starts with, which match `pattern` too.
+/// Every node, that matches `pattern`, is among them, so document isn't searched again.
+pub fn narrow_matches(
+    content: &Document,
+    structure: &JsonMetadata,
+    matches: &[JsonMetadataIndex],
+    pattern: &[u8],
+) -> Vec<JsonMetadataIndex> {
+    let ignore_case = is_ignore_case(pattern);
+    matches
+        .iter()
+        .copied()
+        .filter(|&index| is_match(content, structure.get(index), pattern, ignore_case))
+        .collect()
+}
+
+fn is_ignore_case(pattern: &[u8]) -> bool {
+    !pattern.iter().any(|c| c.is_ascii_uppercase())
+}
+
+/// Key name or scalar value contains `pattern`
+fn is_match(content: &Document, object: ObjectMeta, pattern: &[u8], ignore_case: bool) -> bool {
+    let name_matches = object
+        .name(content)
+        .is_some_and(|name| contains(name, pattern, ignore_case));
+    let value_matches = match object.ty {
+        ObjectType::String => {
+            let source = object.source(content);
+            contains(&source[1..source.len() - 1], pattern, ignore_case)
+        }
+        ObjectType::Number | ObjectType::Bool | ObjectType::Null => {
+            contains(&object.source(content), pattern, ignore_case)
+        }
+        ObjectType::EmptyArray
+        | ObjectType::EmptyStructure
+        | ObjectType::Array
+        | ObjectType::Structure => false,
+    };
+    name_matches || value_matches
+}
+
+/// First match after `current`, wrapping around to the beginning
+pub fn next_match(
+    structure: &JsonMetadata,
+    matches: &[JsonMetadataIndex],
+    current: JsonMetadataIndex,
+) -> Option<JsonMetadataIndex> {
+    let current = structure.source_start(current);
+    let position = matches.partition_point(|&index| structure.source_start(index) <= current);
+    matches.get(position).or(matches.first()).copied()
+}
+
+/// Last match before `current`, wrapping around to the end
+pub fn prev_match(
+    structure: &JsonMetadata,
+    matches: &[JsonMetadataIndex],
+    current: JsonMetadataIndex,
+) -> Option<JsonMetadataIndex> {
+    let current = structure.source_start(current);
+    let position = matches.partition_point(|&index| structure.source_start(index) < current);
+    if position == 0 {
+        matches.last().copied()
+    } else {
+        Some(matches[position - 1])
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8], ignore_case: bool) -> bool {
+    if needle.len() > haystack.len() {
+        return false;
+    }
+    haystack.windows(needle.len()).any(|window| {
+        if ignore_case {
+            window.eq_ignore_ascii_case(needle)
+        } else {
+            window == needle
+        }
+    })
+}
@@ -0,0 +1,174 @@
+use std::{collections::BTreeMap, path::Path};
+
+use crate::*;
+
+/// View state of one document, nodes are stored as paths
+#[derive(Debug, Default)]
+pub struct Session {
+    pub selection: String,
+    /// Parents go before children
+    pub expanded: Vec<String>,
+    pub marks: BTreeMap<char, String>,
+}
+
+impl Session {
+    /// Only containers, which expansion is visible, are stored.
+    /// Ancestors expanded by filter are stored as they were before it.
+    pub fn capture(
+        content: &Document,
+        structure: &JsonMetadata,
+        selection: JsonMetadataIndex,
+        marks: &BTreeMap<char, JsonMetadataIndex>,
+    ) -> Self {
+        let mut expanded = Vec::new();
+        if !structure.is_empty() {
+            let mut stack = vec![JsonMetadataIndex::ROOT];
+            while let Some(index) = stack.pop() {
+                let first = stack.len();
+                for child in structure.children(index) {
+                    if structure.is_expanded_unfiltered(child)
+                        && structure.first_child(child).is_some()
+                    {
+                        expanded.push(path_to(content, structure, child));
+                        stack.push(child);
+                    }
+                }
+                // pre-order, so first child is taken first
+                stack[first..].reverse();
+            }
+        }
+        Session {
+            selection: path_to(content, structure, selection),
+            expanded,
+            marks: marks
+                .iter()
+                .map(|(&letter, &index)| (letter, path_to(content, structure, index)))
+                .collect(),
+        }
+    }
+
+    /// Paths, that don't exist anymore, are skipped
+    /// # Returns selection and marks
+    pub fn restore(
+        &self,
+        content: &Document,
+        structure: &mut JsonMetadata,
+    ) -> (JsonMetadataIndex, BTreeMap<char, JsonMetadataIndex>) {
+        let resolve = |structure: &mut JsonMetadata, path: &str| {
+            resolve_path(content, structure, path)
+                .ok()
+                .and_then(|matches| matches.first().copied())
+        };
+        for path in &self.expanded {
+            if let Some(index) = resolve(structure, path) {
+                // container with invalid json stays collapsed, like after expanding it with keys
+                let _ = structure.set_expanded(content, index, true);
+            }
+        }
+        let selection = resolve(structure, &self.selection).unwrap_or(JsonMetadataIndex::ROOT);
+        structure.expand_ancestors(selection);
+        let marks = self
+            .marks
+            .iter()
+            .filter_map(|(&letter, path)| Some((letter, resolve(structure, path)?)))
+            .collect();
+        (selection, marks)
+    }
+}
+
+/// Session file has sessions of several documents, each starts with `file <size> <path>` line.
+/// Document is identified by its canonical path and size, so changed file doesn't get old session.
+/// # Returns `None` if there is no session for document
+pub fn load_session(
+    session_file: &Path,
+    document: &Path,
+    size: u64,
+) -> std::io::Result<Option<Session>> {
+    let header = session_header(document, size)?;
+    let text = match std::fs::read_to_string(session_file) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    let Some((_, lines)) = split_sessions(&text)
+        .into_iter()
+        .find(|(entry_header, _)| *entry_header == header)
+    else {
+        return Ok(None);
+    };
+    let mut session = Session::default();
+    for line in lines {
+        let Some((kind, value)) = line.split_once(' ') else {
+            continue;
+        };
+        match kind {
+            "selection" => session.selection = value.to_owned(),
+            "expanded" => session.expanded.push(value.to_owned()),
+            "mark" => {
+                let mut chars = value.chars();
+                if let (Some(letter), Some(' ')) = (chars.next(), chars.next()) {
+                    session.marks.insert(letter, chars.as_str().to_owned());
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(Some(session))
+}
+
+/// Replaces session of document, sessions of other documents are kept
+pub fn save_session(
+    session_file: &Path,
+    document: &Path,
+    size: u64,
+    session: &Session,
+) -> std::io::Result<()> {
+    let header = session_header(document, size)?;
+    let text = match std::fs::read_to_string(session_file) {
+        Ok(text) => text,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(err) => return Err(err),
+    };
+    let mut output = String::new();
+    for (entry_header, lines) in split_sessions(&text) {
+        if entry_header != header {
+            output.push_str(entry_header);
+            output.push('\n');
+            for line in lines {
+                output.push_str(line);
+                output.push('\n');
+            }
+        }
+    }
+    output.push_str(&header);
+    output.push('\n');
+    output.push_str(&format!("selection {}\n", session.selection));
+    for path in &session.expanded {
+        output.push_str(&format!("expanded {path}\n"));
+    }
+    for (letter, path) in &session.marks {
+        output.push_str(&format!("mark {letter} {path}\n"));
+    }
+    if let Some(parent) = session_file.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(session_file, output)
+}
+
+fn session_header(document: &Path, size: u64) -> std::io::Result<String> {
+    let path = std::fs::canonicalize(document)?;
+    Ok(format!("file {size} {}", path.display()))
+}
+
+/// # Returns header and other lines of each session
+fn split_sessions(text: &str) -> Vec<(&str, Vec<&str>)> {
+    let mut sessions: Vec<(&str, Vec<&str>)> = Vec::new();
+    for line in text.lines() {
+        if line.starts_with("file ") {
+            sessions.push((line, Vec::new()));
+        } else if let Some((_, lines)) = sessions.last_mut() {
+            lines.push(line);
+        }
+    }
+    sessions
+}
@@ -0,0 +1,187 @@
+use std::{borrow::Cow, cmp::Ordering};
+
+use anyhow::{Result, bail};
+
+use crate::*;
+
+/// Value, that children are compared by. Types are ordered like in jq.
+#[derive(Debug, PartialEq, PartialOrd)]
+enum SortKey<'a> {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(Cow<'a, str>),
+    /// Containers keep document order
+    Container(usize),
+    /// Child has nothing at key path, these are shown last
+    Missing,
+}
+
+impl SortKey<'_> {
+    fn compare(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (SortKey::Number(a), SortKey::Number(b)) => a.total_cmp(b),
+            _ => self.partial_cmp(other).unwrap_or(Ordering::Equal),
+        }
+    }
+}
+
+fn value_key<'a>(
+    content: &'a Document,
+    structure: &JsonMetadata,
+    index: JsonMetadataIndex,
+) -> SortKey<'a> {
+    let object = structure.get(index);
+    let source = || content.token(object.source_start..object.source_start + object.source_len);
+    match object.ty {
+        ObjectType::Null => SortKey::Null,
+        ObjectType::Bool => SortKey::Bool(source() == b"true"),
+        ObjectType::Number => SortKey::Number(
+            str::from_utf8(source())
+                .ok()
+                .and_then(|number| number.parse().ok())
+                .unwrap_or(f64::NAN),
+        ),
+        ObjectType::String => {
+            let source = source();
+            SortKey::String(string_key(&source[1..source.len() - 1]))
+        }
+        ObjectType::EmptyArray
+        | ObjectType::EmptyStructure
+        | ObjectType::Array
+        | ObjectType::Structure => SortKey::Container(object.source_start),
+    }
+}
+
+fn string_key(raw: &[u8]) -> Cow<'_, str> {
+    match str::from_utf8(raw) {
+        Ok(text) => decode_escapes(text),
+        Err(_) => String::from_utf8_lossy(raw),
+    }
+}
+
+impl JsonMetadata {
+    /// Shows children of container at `index` ordered by value at `key` path relative to them,
+    /// like `.name`. Empty `key` orders object members by name and array elements by value.
+    /// Only overview and its navigation use new order, document and data pane are left as is.
+    /// Equal children keep document order, so sorting by a field groups them.
+    pub fn sort_children(
+        &mut self,
+        content: &Document,
+        index: JsonMetadataIndex,
+        key: &str,
+        descending: bool,
+    ) -> Result<()> {
+        if !matches!(self.ty(index), ObjectType::Array | ObjectType::Structure) {
+            bail!("only arrays and objects with children could be sorted");
+        }
+        self.index_children(content, index)?;
+        let children: Vec<_> = self.children(index).collect();
+        let mut keyed = Vec::with_capacity(children.len());
+        for &child in &children {
+            let value = if key.is_empty() {
+                match self.get(child).name(content) {
+                    Some(name) => SortKey::String(string_key(name)),
+                    None => value_key(content, self, child),
+                }
+            } else {
+                match resolve_relative_path(content, self, child, key)?.first() {
+                    Some(&value) => value_key(content, self, value),
+                    None => SortKey::Missing,
+                }
+            };
+            keyed.push((value, child));
+        }
+        keyed.sort_by(|(a, _), (b, _)| match (a, b) {
+            (SortKey::Missing, _) | (_, SortKey::Missing) => a.compare(b),
+            _ if descending => b.compare(a),
+            _ => a.compare(b),
+        });
+        let sorted: Vec<_> = keyed.into_iter().map(|(_, child)| child).collect();
+        self.clear_sort(index);
+        for (position, &child) in sorted.iter().enumerate() {
+            self.sorted_position.insert(child, position as u32);
+        }
+        self.sorted.insert(index, sorted);
+        Ok(())
+    }
+    /// Restores document order of children of `index`
+    /// # Returns false if they weren't sorted
+    pub fn clear_sort(&mut self, index: JsonMetadataIndex) -> bool {
+        let Some(sorted) = self.sorted.remove(&index) else {
+            return false;
+        };
+        for child in sorted {
+            self.sorted_position.remove(&child);
+        }
+        true
+    }
+    pub fn is_sorted(&self, index: JsonMetadataIndex) -> bool {
+        self.sorted.contains_key(&index)
+    }
+    /// Children in order of overview
+    pub fn displayed_children(
+        &self,
+        index: JsonMetadataIndex,
+    ) -> impl Iterator<Item = JsonMetadataIndex> + '_ {
+        std::iter::successors(self.first_displayed_child(index), |&child| {
+            self.next_displayed(child)
+        })
+    }
+    fn first_displayed_child(&self, index: JsonMetadataIndex) -> Option<JsonMetadataIndex> {
+        match self.sorted.get(&index) {
+            Some(sorted) => sorted.first().copied(),
+            None => self.first_child(index),
+        }
+    }
+    pub fn next_displayed(&self, index: JsonMetadataIndex) -> Option<JsonMetadataIndex> {
+        match self.sorted_siblings(index) {
+            Some((sorted, position)) => sorted.get(position + 1).copied(),
+            None => self.next(index),
+        }
+    }
+    pub fn prev_displayed(&self, index: JsonMetadataIndex) -> Option<JsonMetadataIndex> {
+        match self.sorted_siblings(index) {
+            Some((sorted, position)) => position.checked_sub(1).map(|prev| sorted[prev]),
+            None => self.prev(index),
+        }
+    }
+    /// Display order of siblings of `index` and its position in it
+    fn sorted_siblings(&self, index: JsonMetadataIndex) -> Option<(&[JsonMetadataIndex], usize)> {
+        let &position = self.sorted_position.get(&index)?;
+        let sorted = &self.sorted[&self.parent(index)?];
+        Some((sorted, position as usize))
+    }
+    /// Compares nodes by position in overview, which is document order unless containers are sorted
+    pub fn displayed_before(&self, a: JsonMetadataIndex, b: JsonMetadataIndex) -> bool {
+        if self.sorted.is_empty() {
+            return self.source_start(a) < self.source_start(b);
+        }
+        let ancestors = |index| {
+            let mut ancestors: Vec<_> =
+                std::iter::successors(Some(index), |&index| self.parent(index)).collect();
+            ancestors.reverse();
+            ancestors
+        };
+        let (a_ancestors, b_ancestors) = (ancestors(a), ancestors(b));
+        let common = a_ancestors
+            .iter()
+            .zip(&b_ancestors)
+            .take_while(|(a, b)| a == b)
+            .count();
+        match (a_ancestors.get(common), b_ancestors.get(common)) {
+            // siblings under common ancestor
+            (Some(&a), Some(&b)) => match self
+                .sorted_position
+                .get(&a)
+                .zip(self.sorted_position.get(&b))
+            {
+                Some((a, b)) => a < b,
+                None => self.source_start(a) < self.source_start(b),
+            },
+            // ancestor is above its descendants
+            (None, Some(_)) => true,
+            (_, None) => false,
+        }
+    }
+}
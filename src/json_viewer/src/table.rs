@@ -0,0 +1,387 @@
+use std::collections::HashSet;
+
+use anyhow::{Result, bail};
+
+use crate::*;
+
+/// Longer values are cut with ellipsis
+const MAX_COLUMN_WIDTH: usize = 30;
+/// Spaces between columns
+const GAP: &str = "  ";
+
+struct Column {
+    /// Raw(still escaped) key name
+    name: Vec<u8>,
+    /// Widest value or name, up to `MAX_COLUMN_WIDTH`
+    width: usize,
+    hidden: bool,
+}
+
+/// Array of objects shown in data pane as table: elements are rows, keys are columns.
+/// Rows follow order of overview, so sorted and filtered arrays are shown sorted and filtered.
+/// Keys of rows are collected, when rows are scrolled into view.
+pub struct Table {
+    array: JsonMetadataIndex,
+    rows: Vec<JsonMetadataIndex>,
+    /// Rows, which keys are in `columns`
+    collected: HashSet<JsonMetadataIndex>,
+    /// Union of keys of collected rows in order of first appearance
+    columns: Vec<Column>,
+    /// Digits of the last index of array
+    gutter_width: usize,
+    row: usize,
+    /// Cursor column, always visible
+    column: usize,
+    /// First shown row
+    top: usize,
+    /// First shown column
+    left: usize,
+}
+
+impl Table {
+    pub fn new(
+        content: &Document,
+        structure: &mut JsonMetadata,
+        array: JsonMetadataIndex,
+    ) -> Result<Self> {
+        if structure.ty(array) != ObjectType::Array {
+            bail!("only arrays of objects could be shown as table");
+        }
+        structure.index_children(content, array)?;
+        let rows = shown_rows(structure, array);
+        if rows.iter().any(|&row| {
+            !matches!(
+                structure.ty(row),
+                ObjectType::Structure | ObjectType::EmptyStructure
+            )
+        }) {
+            bail!("only arrays of objects could be shown as table");
+        }
+        let Some(&first) = rows
+            .iter()
+            .find(|&&row| structure.ty(row) == ObjectType::Structure)
+        else {
+            bail!("array has no objects with keys");
+        };
+        let mut table = Table {
+            array,
+            rows,
+            collected: HashSet::new(),
+            columns: Vec::new(),
+            gutter_width: gutter_width(structure, array),
+            row: 0,
+            column: 0,
+            top: 0,
+            left: 0,
+        };
+        // cursor column exists from the start
+        table.collect_columns(content, structure, first)?;
+        Ok(table)
+    }
+
+    /// Adds keys of `row` to columns, widths grow to fit its values
+    fn collect_columns(
+        &mut self,
+        content: &Document,
+        structure: &mut JsonMetadata,
+        row: JsonMetadataIndex,
+    ) -> Result<()> {
+        if self.collected.contains(&row) {
+            return Ok(());
+        }
+        structure.index_children(content, row)?;
+        self.collected.insert(row);
+        for member in structure.children(row) {
+            let object = structure.get(member);
+            let name = object.name(content).unwrap();
+            let position = match self.columns.iter().position(|column| column.name == name) {
+                Some(position) => position,
+                None => {
+                    self.columns.push(Column {
+                        name: name.to_vec(),
+                        width: display_width(&display_text(&String::from_utf8_lossy(name), false)),
+                        hidden: false,
+                    });
+                    self.columns.len() - 1
+                }
+            };
+            let column = &mut self.columns[position];
+            column.width = column
+                .width
+                .max(display_width(&cell_text(content, object, false)))
+                .min(MAX_COLUMN_WIDTH);
+        }
+        Ok(())
+    }
+
+    /// Builds table again after edit, keeping cursor and hidden columns
+    pub fn reopen(self, content: &Document, structure: &mut JsonMetadata) -> Result<Self> {
+        let mut table = Table::new(content, structure, self.array)?;
+        for column in &mut table.columns {
+            column.hidden = self
+                .columns
+                .iter()
+                .any(|old| old.hidden && old.name == column.name);
+        }
+        // removed row is replaced with the next one
+        let cursor_row = self.rows.get(self.row);
+        table.row = (0..table.rows.len())
+            .find(|&row| Some(&table.rows[row]) == cursor_row)
+            .unwrap_or(self.row)
+            .min(table.rows.len().saturating_sub(1));
+        let cursor_name = &self.columns[self.column].name;
+        table.column = table
+            .columns
+            .iter()
+            .position(|column| &column.name == cursor_name)
+            .or_else(|| table.visible_columns().next())
+            .unwrap_or(0);
+        table.left = table.column;
+        Ok(table)
+    }
+
+    pub fn array(&self) -> JsonMetadataIndex {
+        self.array
+    }
+
+    /// Rows are taken again, after children of array were sorted or filtered
+    pub fn refresh_rows(&mut self, structure: &JsonMetadata) {
+        self.rows = shown_rows(structure, self.array);
+        self.row = self.row.min(self.rows.len().saturating_sub(1));
+    }
+
+    /// Value under cursor, or row if it has no such key
+    pub fn focused_node(&self, content: &Document, structure: &JsonMetadata) -> JsonMetadataIndex {
+        let Some(&row) = self.rows.get(self.row) else {
+            return self.array;
+        };
+        self.cell(content, structure, row, self.column)
+            .unwrap_or(row)
+    }
+
+    fn cell(
+        &self,
+        content: &Document,
+        structure: &JsonMetadata,
+        row: JsonMetadataIndex,
+        column: usize,
+    ) -> Option<JsonMetadataIndex> {
+        let name = self.columns.get(column)?.name.as_slice();
+        structure
+            .children(row)
+            .find(|&member| structure.get(member).name(content) == Some(name))
+    }
+
+    fn visible_columns(&self) -> impl DoubleEndedIterator<Item = usize> + '_ {
+        (0..self.columns.len()).filter(|&column| !self.columns[column].hidden)
+    }
+
+    pub fn move_rows(&mut self, delta: isize) {
+        let last = self.rows.len().saturating_sub(1);
+        self.row = self.row.saturating_add_signed(delta).min(last);
+    }
+    pub fn first_row(&mut self) {
+        self.row = 0;
+    }
+    pub fn last_row(&mut self) {
+        self.row = self.rows.len().saturating_sub(1);
+    }
+    pub fn next_column(&mut self) {
+        let current = self.column;
+        let next = self.visible_columns().find(|&column| column > current);
+        self.column = next.unwrap_or(current);
+    }
+    pub fn prev_column(&mut self) {
+        let current = self.column;
+        let prev = self.visible_columns().rfind(|&column| column < current);
+        self.column = prev.unwrap_or(current);
+    }
+
+    /// Hides cursor column, cursor moves to the next one
+    pub fn hide_column(&mut self) -> Result<()> {
+        if self.visible_columns().count() == 1 {
+            bail!("last column can't be hidden");
+        }
+        let hidden = self.column;
+        self.columns[hidden].hidden = true;
+        let next = self.visible_columns().find(|&column| column > hidden);
+        let last = self.visible_columns().next_back();
+        self.column = next.or(last).unwrap();
+        Ok(())
+    }
+    /// # Returns number of columns, that were hidden
+    pub fn show_columns(&mut self) -> usize {
+        let mut shown = 0;
+        for column in &mut self.columns {
+            shown += column.hidden as usize;
+            column.hidden = false;
+        }
+        shown
+    }
+
+    /// Moves cursor to cell at `line` of rendered table and `x` column of data pane
+    pub fn click(&mut self, line: usize, x: usize) {
+        // the first line is header
+        let row = self.top + line;
+        if line == 0 || row > self.rows.len() {
+            return;
+        }
+        self.row = row - 1;
+        let mut end = self.gutter_width + GAP.len();
+        let clicked = self
+            .visible_columns()
+            .filter(|&column| column >= self.left)
+            .find(|&column| {
+                end += self.columns[column].width + GAP.len();
+                x < end
+            });
+        // gutter and space after the last column keep cursor column
+        if let Some(column) = clicked
+            && x >= self.gutter_width + GAP.len()
+        {
+            self.column = column;
+        }
+    }
+
+    /// Scrolls, so cursor is shown in `width` columns and `height` lines, including header.
+    /// Keys of shown rows are collected, so their columns are rendered.
+    pub fn scroll_to_cursor(
+        &mut self,
+        content: &Document,
+        structure: &mut JsonMetadata,
+        width: usize,
+        height: usize,
+    ) -> Result<()> {
+        let rows_height = height.saturating_sub(1).max(1);
+        self.top = self
+            .top
+            .min(self.row)
+            .max((self.row + 1).saturating_sub(rows_height));
+        let shown = self.top..(self.top + rows_height).min(self.rows.len());
+        for position in shown {
+            self.collect_columns(content, structure, self.rows[position])?;
+        }
+        self.left = self.left.min(self.column);
+        let used = |left: usize| {
+            self.visible_columns()
+                .filter(|&column| left <= column && column <= self.column)
+                .map(|column| self.columns[column].width + GAP.len())
+                .sum::<usize>()
+                + self.gutter_width
+                + GAP.len()
+        };
+        let mut left = self.left;
+        while left < self.column && used(left) > width {
+            left += 1;
+        }
+        self.left = left;
+        Ok(())
+    }
+
+    /// Header and rows from `top`, cells are cut to width of their columns
+    pub fn render(
+        &self,
+        content: &Document,
+        structure: &JsonMetadata,
+        (width, height): (usize, usize),
+        theme: &Theme,
+        decode_escapes: bool,
+        focused: bool,
+    ) -> Vec<String> {
+        let gutter_width = self.gutter_width;
+        // columns after the first one cut by the edge aren't rendered
+        let mut shown_columns = Vec::new();
+        let mut used = gutter_width + GAP.len();
+        for column in self.visible_columns().filter(|&column| column >= self.left) {
+            shown_columns.push(column);
+            used += self.columns[column].width + GAP.len();
+            if used >= width {
+                break;
+            }
+        }
+        let mut lines = Vec::with_capacity(height);
+        let mut header = format!("{:>gutter_width$}{GAP}{}", "#", theme.key);
+        for &column in &shown_columns {
+            let name = String::from_utf8_lossy(&self.columns[column].name);
+            header.push_str(&fit_to_width(
+                &display_text(&name, decode_escapes),
+                0,
+                self.columns[column].width,
+            ));
+            header.push_str(GAP);
+        }
+        header.push_str(RESET);
+        lines.push(header);
+        let rows = self.rows.iter().enumerate().skip(self.top);
+        for (position, &row) in rows.take(height.saturating_sub(1)) {
+            let index = match structure.get(row).name_or_index {
+                NameOrIndex::Index(index) => index,
+                NameOrIndex::Name { .. } | NameOrIndex::Root => {
+                    unreachable!("rows are array elements")
+                }
+            };
+            let mut line = format!("{index:>gutter_width$}{GAP}");
+            for &column in &shown_columns {
+                let text = match self.cell(content, structure, row, column) {
+                    Some(cell) => {
+                        let object = structure.get(cell);
+                        format!(
+                            "{}{}",
+                            value_style(theme, object.ty),
+                            cell_text(content, object, decode_escapes)
+                        )
+                    }
+                    None => String::new(),
+                };
+                let text = fit_to_width(&text, 0, self.columns[column].width);
+                if focused && position == self.row && column == self.column {
+                    line.push_str(&format!("{}{text}{RESET}", theme.selection));
+                } else {
+                    line.push_str(&format!("{text}{RESET}"));
+                }
+                line.push_str(GAP);
+            }
+            lines.push(line);
+        }
+        lines
+    }
+}
+
+/// Children of array, that aren't hidden by filter, in order of overview
+fn shown_rows(structure: &JsonMetadata, array: JsonMetadataIndex) -> Vec<JsonMetadataIndex> {
+    structure
+        .displayed_children(array)
+        .filter(|&row| structure.is_shown(row))
+        .collect()
+}
+
+/// Width of indices of all elements, so it doesn't change with filter
+fn gutter_width(structure: &JsonMetadata, array: JsonMetadataIndex) -> usize {
+    let elements = structure.children(array).count();
+    elements.saturating_sub(1).to_string().len()
+}
+
+/// Scalars as in document, containers folded
+fn cell_text(content: &Document, object: ObjectMeta, decode_escapes: bool) -> String {
+    match object.ty {
+        ObjectType::Array => "[…]".to_owned(),
+        ObjectType::Structure => "{…}".to_owned(),
+        ObjectType::EmptyArray => "[]".to_owned(),
+        ObjectType::EmptyStructure => "{}".to_owned(),
+        _ => display_text(
+            &String::from_utf8_lossy(&object.source(content)),
+            decode_escapes,
+        )
+        .into_owned(),
+    }
+}
+
+fn value_style(theme: &Theme, ty: ObjectType) -> &str {
+    match ty {
+        ObjectType::String => &theme.string,
+        ObjectType::Number => &theme.number,
+        ObjectType::Bool => &theme.bool,
+        ObjectType::Null => &theme.null,
+        _ => "",
+    }
+}
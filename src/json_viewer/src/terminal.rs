@@ -0,0 +1,85 @@
+use std::{
+    fs::File,
+    io::{Read, Write},
+    panic::{set_hook, take_hook},
+    sync::Mutex,
+};
+
+use rustix::termios::Termios;
+
+static ORIGINAL_MODE: Mutex<Option<Termios>> = Mutex::new(None);
+
+/// Alternate screen in raw mode on controlling terminal.
+/// Unlike `alternate_screen_wrapper`, doesn't touch stdin and stdout, so they can be piped.
+/// Terminal is restored on drop and on panic.
+pub struct Terminal {
+    tty: File,
+}
+impl Terminal {
+    pub fn enter() -> std::io::Result<Self> {
+        let mut mode = ORIGINAL_MODE.lock().unwrap_or_else(|err| err.into_inner());
+        assert!(mode.is_none(), "terminal is already entered");
+
+        let mut tty = File::options().read(true).write(true).open("/dev/tty")?;
+        init_panic_hook();
+        tty.write_all(b"\x1B[?1049h")?;
+        let mut termios = rustix::termios::tcgetattr(&tty)?;
+        *mode = Some(termios.clone());
+        termios.make_raw();
+        rustix::termios::tcsetattr(&tty, rustix::termios::OptionalActions::Now, &termios)?;
+        // hide cursor, disable line wrap, report clicks, drags and wheel in SGR format
+        tty.write_all(b"\x1B[?25l\x1B[?7l\x1B[?1002h\x1B[?1006h")?;
+        Ok(Terminal { tty })
+    }
+    /// Waits for input and reads it into `buf`.
+    /// # Returns None if interrupted by signal, like resize
+    pub fn read_input<'a>(&self, buf: &'a mut [u8]) -> std::io::Result<Option<&'a [u8]>> {
+        // unlike read, poll is interrupted by resize signal
+        let mut poll_fds = [rustix::event::PollFd::new(
+            &self.tty,
+            rustix::event::PollFlags::IN,
+        )];
+        match rustix::event::poll(&mut poll_fds, None) {
+            Ok(_) => {}
+            Err(rustix::io::Errno::INTR) => return Ok(None),
+            Err(err) => return Err(err.into()),
+        }
+        let count = (&self.tty).read(buf)?;
+        Ok(Some(&buf[..count]))
+    }
+    /// For reading keys and drawing
+    pub fn tty(&self) -> &File {
+        &self.tty
+    }
+}
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        if let Err(err) = restore_terminal() {
+            eprintln!("Error: {err}");
+        }
+    }
+}
+
+fn restore_terminal() -> std::io::Result<()> {
+    let Some(original_mode) = ORIGINAL_MODE
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+        .take()
+    else {
+        return Ok(());
+    };
+    let mut tty = File::options().write(true).open("/dev/tty")?;
+    rustix::termios::tcsetattr(&tty, rustix::termios::OptionalActions::Now, &original_mode)?;
+    // stop mouse reports, show cursor, enable line wrap, leave alternate screen
+    tty.write_all(b"\x1B[?1006l\x1B[?1002l\x1B[?25h\x1B[?7h\x1B[?1049l")?;
+    tty.flush()
+}
+
+fn init_panic_hook() {
+    let original_hook = take_hook();
+    set_hook(Box::new(move |panic_info| {
+        // intentionally ignore errors here since we're already in a panic
+        let _ = restore_terminal();
+        original_hook(panic_info);
+    }));
+}
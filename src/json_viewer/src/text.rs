@@ -0,0 +1,167 @@
+use std::{borrow::Cow, fmt::Write};
+
+use unicode_width::UnicodeWidthChar;
+
+/// Display width of line with escape sequences
+pub fn display_width(line: &str) -> usize {
+    let mut width = 0;
+    for_each_segment(line, |segment| {
+        if let Segment::Char(ch) = segment {
+            width += ch.width().unwrap_or(0);
+        }
+    });
+    width
+}
+
+/// Cuts `skip` columns from the start and fits the rest into exactly `width` columns,
+/// marking cut off content with ellipsis and padding with spaces.
+/// Escape sequences are preserved, wide characters split at the edge are replaced with spaces.
+pub fn fit_to_width(line: &str, skip: usize, width: usize) -> String {
+    let total = display_width(line);
+    let left_ellipsis = width > 0 && skip > 0 && total > skip;
+    let right_ellipsis = width > left_ellipsis as usize && total > skip + width;
+    let start = skip + left_ellipsis as usize;
+    let end = skip + width - right_ellipsis as usize;
+
+    let mut output = String::with_capacity(line.len() + width);
+    if left_ellipsis {
+        output.push('…');
+    }
+    let mut column = 0;
+    let mut filled = 0;
+    for_each_segment(line, |segment| match segment {
+        Segment::Escape(escape) => output.push_str(escape),
+        Segment::Char(ch) => {
+            let ch_width = ch.width().unwrap_or(0);
+            let ch_end = column + ch_width;
+            if column >= start && ch_end <= end {
+                output.push(ch);
+                filled += ch_width;
+            } else if column < end && ch_end > start {
+                let visible = ch_end.min(end) - column.max(start);
+                output.extend(std::iter::repeat_n(' ', visible));
+                filled += visible;
+            }
+            column = ch_end;
+        }
+    });
+    output.extend(std::iter::repeat_n(' ', (end - start) - filled));
+    if right_ellipsis {
+        output.push('…');
+    }
+    output
+}
+
+enum Segment<'a> {
+    Escape(&'a str),
+    Char(char),
+}
+
+/// Splits line into CSI escape sequences and characters
+fn for_each_segment<'a>(line: &'a str, mut f: impl FnMut(Segment<'a>)) {
+    let mut rest = line;
+    while let Some(ch) = rest.chars().next() {
+        if ch == '\x1b'
+            && rest.as_bytes().get(1) == Some(&b'[')
+            && let Some(final_position) = rest.as_bytes()[2..]
+                .iter()
+                .position(|byte| (0x40..=0x7e).contains(byte))
+        {
+            let (escape, tail) = rest.split_at(final_position + 3);
+            f(Segment::Escape(escape));
+            rest = tail;
+        } else {
+            f(Segment::Char(ch));
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+}
+
+/// Replaces control characters with visible escapes, so user content can't inject
+/// terminal escape sequences. Bidirectional overrides are escaped too, as they reorder display.
+pub fn sanitize(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(needs_escaping) {
+        return Cow::Borrowed(text);
+    }
+    let mut output = String::with_capacity(text.len() + 8);
+    for ch in text.chars() {
+        match ch {
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            ch if needs_escaping(ch) => {
+                write!(output, "\\u{:04x}", ch as u32).unwrap();
+            }
+            ch => output.push(ch),
+        }
+    }
+    Cow::Owned(output)
+}
+
+/// User content prepared for terminal
+pub fn display_text(raw: &str, decode: bool) -> Cow<'_, str> {
+    if decode {
+        Cow::Owned(sanitize(&decode_escapes(raw)).into_owned())
+    } else {
+        sanitize(raw)
+    }
+}
+
+fn needs_escaping(ch: char) -> bool {
+    ch.is_control() || matches!(ch, '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Decodes JSON escapes(`\n`, `\"`, `\u00e9`, surrogate pairs) inside of string.
+/// Invalid escapes are kept as is. Result should be sanitized before display.
+pub fn decode_escapes(text: &str) -> Cow<'_, str> {
+    if !text.contains('\\') {
+        return Cow::Borrowed(text);
+    }
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(position) = rest.find('\\') {
+        output.push_str(&rest[..position]);
+        rest = &rest[position..];
+        let (decoded, len) = match rest.as_bytes().get(1) {
+            Some(b'"') => (Some('"'), 2),
+            Some(b'\\') => (Some('\\'), 2),
+            Some(b'/') => (Some('/'), 2),
+            Some(b'b') => (Some('\x08'), 2),
+            Some(b'f') => (Some('\x0c'), 2),
+            Some(b'n') => (Some('\n'), 2),
+            Some(b'r') => (Some('\r'), 2),
+            Some(b't') => (Some('\t'), 2),
+            Some(b'u') => decode_unicode_escape(rest),
+            _ => (None, 1),
+        };
+        match decoded {
+            Some(ch) => output.push(ch),
+            None => output.push_str(&rest[..len]),
+        }
+        rest = &rest[len..];
+    }
+    output.push_str(rest);
+    Cow::Owned(output)
+}
+
+/// Decodes `\uXXXX` or surrogate pair `\uXXXX\uXXXX` at the start of `text`
+fn decode_unicode_escape(text: &str) -> (Option<char>, usize) {
+    let code_unit = |position: usize| {
+        text.get(position..position + 6)
+            .and_then(|escape| escape.strip_prefix("\\u"))
+            .and_then(|hex| u16::from_str_radix(hex, 16).ok())
+    };
+    let Some(high) = code_unit(0) else {
+        return (None, 2);
+    };
+    if let Some(ch) = char::from_u32(high as u32) {
+        return (Some(ch), 6);
+    }
+    match code_unit(6) {
+        Some(low) => match char::decode_utf16([high, low]).next() {
+            Some(Ok(ch)) => (Some(ch), 12),
+            _ => (None, 6),
+        },
+        None => (None, 6),
+    }
+}
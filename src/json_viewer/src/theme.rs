@@ -0,0 +1,199 @@
+use std::str::FromStr;
+
+use anyhow::bail;
+
+/// How many colours terminal supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorDepth {
+    Ansi16,
+    Ansi256,
+    TrueColor,
+}
+impl ColorDepth {
+    /// Guessed from `COLORTERM` and `TERM`
+    pub fn detect() -> Self {
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        let term = std::env::var("TERM").unwrap_or_default();
+        if colorterm == "truecolor" || colorterm == "24bit" {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Ansi256
+        } else {
+            ColorDepth::Ansi16
+        }
+    }
+}
+
+/// Colour with one of 8 basic colours as fallback
+#[derive(Debug, Clone, Copy)]
+struct Color {
+    rgb: [u8; 3],
+    ansi: u8,
+}
+impl Color {
+    const fn new(rgb: [u8; 3], ansi: u8) -> Self {
+        Color { rgb, ansi }
+    }
+    fn fg(self, depth: ColorDepth) -> String {
+        self.sequence(depth, 3)
+    }
+    fn bg(self, depth: ColorDepth) -> String {
+        self.sequence(depth, 4)
+    }
+    /// `layer` is 3 for foreground and 4 for background
+    fn sequence(self, depth: ColorDepth, layer: u8) -> String {
+        let [r, g, b] = self.rgb;
+        match depth {
+            ColorDepth::Ansi16 => format!("\x1b[{layer}{}m", self.ansi),
+            ColorDepth::Ansi256 => format!("\x1b[{layer}8;5;{}m", rgb_to_256(self.rgb)),
+            ColorDepth::TrueColor => format!("\x1b[{layer}8;2;{r};{g};{b}m"),
+        }
+    }
+}
+
+/// Nearest colour of xterm colour cube or grayscale ramp
+fn rgb_to_256(rgb: [u8; 3]) -> u8 {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let distance = |other: [u8; 3]| -> u32 {
+        rgb.iter()
+            .zip(other)
+            .map(|(&a, b)| (a as i32 - b as i32).unsigned_abs().pow(2))
+            .sum()
+    };
+    let cube = rgb.map(|channel| {
+        (0..LEVELS.len())
+            .min_by_key(|&level| (LEVELS[level] as i32 - channel as i32).abs())
+            .unwrap()
+    });
+    let cube_color = cube.map(|level| LEVELS[level]);
+    let average = rgb.iter().map(|&channel| channel as u32).sum::<u32>() / 3;
+    let gray = (average.saturating_sub(8) / 10).min(23) as u8;
+    let gray_value = 8 + gray * 10;
+    if distance([gray_value; 3]) < distance(cube_color) {
+        232 + gray
+    } else {
+        16 + 36 * cube[0] as u8 + 6 * cube[1] as u8 + cube[2] as u8
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeName {
+    Dark,
+    Light,
+    /// Without colours, only italic, bold, underline and reverse video
+    Monochrome,
+}
+impl FromStr for ThemeName {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> anyhow::Result<Self> {
+        Ok(match name {
+            "dark" => ThemeName::Dark,
+            "light" => ThemeName::Light,
+            "monochrome" => ThemeName::Monochrome,
+            _ => bail!("unknown theme '{name}', expected dark, light or monochrome"),
+        })
+    }
+}
+
+pub const ITALIC: &str = "\x1b[3m";
+pub const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const UNDERLINE: &str = "\x1b[4m";
+const REVERSE: &str = "\x1b[7m";
+const STRIKETHROUGH: &str = "\x1b[9m";
+
+/// Escape sequences of highlighted parts, empty if part isn't highlighted.
+/// Selected line has only `selection` style, so its text is readable.
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub string: String,
+    pub number: String,
+    pub bool: String,
+    pub null: String,
+    /// `arr` and `obj` in overview
+    pub container: String,
+    /// Keys in data pane
+    pub key: String,
+    /// Annotations of values in data pane, like dates of timestamps
+    pub hint: String,
+    pub selection: String,
+    pub changed: String,
+    pub added: String,
+    pub removed: String,
+    pub modified: String,
+}
+
+impl Theme {
+    pub fn new(name: ThemeName, depth: ColorDepth) -> Self {
+        let palette = match name {
+            ThemeName::Dark => Palette {
+                green: Color::new([152, 195, 121], 2),
+                red: Color::new([224, 108, 117], 1),
+                blue: Color::new([97, 175, 239], 4),
+                cyan: Color::new([86, 182, 194], 6),
+                yellow: Color::new([229, 192, 123], 3),
+                selection: Color::new([38, 79, 120], 4),
+            },
+            ThemeName::Light => Palette {
+                green: Color::new([0, 128, 0], 2),
+                red: Color::new([175, 0, 0], 1),
+                blue: Color::new([0, 0, 215], 4),
+                cyan: Color::new([0, 135, 135], 6),
+                yellow: Color::new([135, 95, 0], 3),
+                selection: Color::new([204, 224, 255], 7),
+            },
+            ThemeName::Monochrome => {
+                return Theme {
+                    string: String::new(),
+                    number: String::new(),
+                    bool: ITALIC.to_owned(),
+                    null: ITALIC.to_owned(),
+                    container: ITALIC.to_owned(),
+                    key: BOLD.to_owned(),
+                    hint: format!("{DIM}{ITALIC}"),
+                    selection: REVERSE.to_owned(),
+                    changed: UNDERLINE.to_owned(),
+                    added: BOLD.to_owned(),
+                    removed: STRIKETHROUGH.to_owned(),
+                    modified: String::new(),
+                };
+            }
+        };
+        Theme {
+            string: palette.green.fg(depth),
+            number: palette.red.fg(depth),
+            bool: format!("{ITALIC}{}", palette.red.fg(depth)),
+            null: palette.blue.fg(depth),
+            container: format!("{ITALIC}{}", palette.cyan.fg(depth)),
+            key: format!("{ITALIC}{}", palette.yellow.fg(depth)),
+            hint: format!("{DIM}{ITALIC}"),
+            selection: palette.selection.bg(depth),
+            changed: palette.yellow.fg(depth),
+            added: palette.green.fg(depth),
+            removed: palette.red.fg(depth),
+            modified: palette.cyan.fg(depth),
+        }
+    }
+
+    /// Theme `name` if given, otherwise monochrome if `NO_COLOR` is set, otherwise dark
+    pub fn from_env(name: Option<ThemeName>) -> Self {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        let name = name.unwrap_or(if no_color {
+            ThemeName::Monochrome
+        } else {
+            ThemeName::Dark
+        });
+        Theme::new(name, ColorDepth::detect())
+    }
+}
+
+struct Palette {
+    green: Color,
+    red: Color,
+    blue: Color,
+    cyan: Color,
+    yellow: Color,
+    selection: Color,
+}
@@ -0,0 +1,1541 @@
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{outline::*, *};
+
+/// Settings of `Viewer`, that don't change while it runs
+pub struct ViewerOptions {
+    /// File of document, `None` for stdin. Edits are saved to it.
+    pub path: Option<PathBuf>,
+    /// Yanked text is written to it instead of terminal clipboard
+    pub yank_file: Option<PathBuf>,
+    /// Document was normalized from JSON5/JSONC, so it can't be edited
+    pub lenient: bool,
+    pub theme: Theme,
+}
+
+/// Requested by quit keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    /// `q`, focused node is printed with `--print-on-exit`
+    Quit,
+    /// `Q`, nothing is printed
+    QuitWithoutPrinting,
+}
+
+/// Keys in and frames out, so the same terminal loop and tests drive viewers of document and diff
+pub trait Interactive {
+    /// # Returns `height` lines, the last one is status line
+    fn render(&mut self) -> Vec<String>;
+    fn handle_key(&mut self, key: Key) -> Option<Exit>;
+    /// `width` and `height` are size of the whole frame, including status line
+    fn resize(&mut self, width: u16, height: u16);
+    /// Escape sequences for terminal, written since last call
+    fn take_terminal_output(&mut self) -> Vec<u8> {
+        Vec::new()
+    }
+}
+
+/// Interactive viewer of one document without terminal:
+/// takes keys with `handle_key` and produces frames with `render`.
+/// Escape sequences, that should be sent to terminal(clipboard), are taken with `take_terminal_output`.
+pub struct Viewer {
+    content: Document,
+    structure: JsonMetadata,
+    options: ViewerOptions,
+    width: u16,
+    height: u16,
+
+    scroll: JsonMetadataIndex,
+    selection: JsonMetadataIndex,
+    // document or its schema, swapped with `S`; schema is inferred again only after edits
+    other_view: Option<View>,
+    schema_shown: bool,
+    marks: BTreeMap<char, JsonMetadataIndex>,
+
+    mode: InputMode,
+    search: Option<Search>,
+    // first key of two key commands, like `zM`
+    pending_prefix: Option<char>,
+    // shown instead of path until next key
+    message: Option<String>,
+    panes: Panes,
+
+    patch: Patch,
+    embedded: Embedded,
+    // set by first quit with unsaved edits
+    quit_warned: bool,
+    terminal_output: Vec<u8>,
+}
+
+impl Viewer {
+    /// `width` and `height` are size of the whole frame, including status line
+    pub fn new(
+        content: Document,
+        structure: JsonMetadata,
+        width: u16,
+        height: u16,
+        options: ViewerOptions,
+    ) -> Self {
+        let panes = Panes::new(width, options.theme.clone());
+        Viewer {
+            content,
+            structure,
+            options,
+            width,
+            height,
+            scroll: JsonMetadataIndex::ROOT,
+            selection: JsonMetadataIndex::ROOT,
+            other_view: None,
+            schema_shown: false,
+            marks: BTreeMap::new(),
+            mode: InputMode::Normal,
+            search: None,
+            pending_prefix: None,
+            message: None,
+            panes,
+            patch: Patch::default(),
+            embedded: Embedded::default(),
+            quit_warned: false,
+            terminal_output: Vec::new(),
+        }
+    }
+
+    pub fn content(&self) -> &Document {
+        &self.content
+    }
+    pub fn structure(&self) -> &JsonMetadata {
+        &self.structure
+    }
+    pub fn selection(&self) -> JsonMetadataIndex {
+        self.selection
+    }
+    /// Node under cursor of focused pane
+    pub fn focused_node(&self) -> JsonMetadataIndex {
+        self.panes
+            .focused_node(&self.content, &self.structure, self.selection)
+    }
+    /// Has edits, that aren't saved
+    pub fn is_modified(&self) -> bool {
+        self.patch.is_modified()
+    }
+
+    /// Shown in status line until next key
+    pub fn set_message(&mut self, message: String) {
+        self.message = Some(message);
+    }
+
+    pub fn restore_session(&mut self, session: &Session) {
+        (self.selection, self.marks) = session.restore(&self.content, &mut self.structure);
+        if let Err(err) = self.structure.index_children(&self.content, self.selection) {
+            self.message = Some(err.to_string());
+        }
+        self.scroll = center_on(&self.structure, self.selection, self.overview_height());
+        self.panes.data_top = DataLine::Open(self.selection);
+        self.panes.data_cursor = DataLine::Open(self.selection);
+    }
+
+    /// Session of document, even if schema is shown
+    pub fn session(&self) -> Session {
+        match &self.other_view {
+            Some(document_view) if self.schema_shown => Session::capture(
+                &document_view.content,
+                &document_view.structure,
+                document_view.selection,
+                &document_view.marks,
+            ),
+            _ => Session::capture(&self.content, &self.structure, self.selection, &self.marks),
+        }
+    }
+
+    /// Writes focused node as indented json
+    pub fn write_focused(&mut self, output: &mut impl Write) -> std::io::Result<()> {
+        let focused = self.focused_node();
+        write_pretty(output, &self.content, &mut self.structure, focused)
+    }
+
+    fn overview_height(&self) -> u16 {
+        // last line is status line
+        self.height.saturating_sub(1)
+    }
+
+    /// Why edits and saving are disabled
+    fn read_only(&self) -> Option<&'static str> {
+        if self.options.lenient {
+            // edits would be saved without comments
+            Some("lenient input can't be edited")
+        } else if self.schema_shown {
+            Some("schema can't be edited")
+        } else if !self.embedded.is_empty() {
+            // decoded json isn't in document and edits would move strings it's read at
+            Some("decoded strings should be encoded back with 'x' before edits")
+        } else {
+            None
+        }
+    }
+
+    fn handle_prompt_key(&mut self, key: Key, effects: &mut KeyEffects) {
+        let InputMode::Prompt(prompt) = &mut self.mode else {
+            return;
+        };
+        let content = &self.content;
+        let structure = &mut self.structure;
+        let panes = &mut self.panes;
+        match key {
+            Key::Enter if prompt.kind == PromptKind::Edit => {
+                let input = std::mem::take(&mut prompt.input);
+                self.mode = InputMode::Normal;
+                let target = panes.focused_node(content, structure, self.selection);
+                effects.edited =
+                    Some(
+                        self.patch
+                            .replace_value(&mut self.content, structure, target, &input),
+                    );
+            }
+            Key::Enter if prompt.kind == PromptKind::Export => {
+                let path = std::mem::take(&mut prompt.input);
+                self.mode = InputMode::Normal;
+                let source = structure
+                    .get(panes.focused_node(content, structure, self.selection))
+                    .source(content);
+                self.message = Some(match std::fs::write(&path, &source) {
+                    Ok(()) => format!("written {} bytes to {path}", source.len()),
+                    Err(err) => format!("can't write {path}: {err}"),
+                });
+            }
+            // `-.name` sorts in descending order
+            Key::Enter if prompt.kind == PromptKind::Sort => {
+                let input = std::mem::take(&mut prompt.input);
+                self.mode = InputMode::Normal;
+                let input = input.trim();
+                let (key, descending) = match input.strip_prefix('-') {
+                    Some(key) => (key, true),
+                    None => (input, false),
+                };
+                match structure.sort_children(content, self.selection, key, descending) {
+                    Ok(()) => {
+                        if let Some(table) = &mut panes.table {
+                            table.refresh_rows(structure);
+                        }
+                        // children are indexed by sorting
+                        structure.expand(self.selection);
+                    }
+                    Err(err) => self.message = Some(err.to_string()),
+                }
+            }
+            // filter is already applied, empty query clears it
+            Key::Enter if prompt.kind == PromptKind::Filter => {
+                if prompt.input.is_empty() {
+                    effects.refilter = Some(String::new());
+                }
+                self.mode = InputMode::Normal;
+            }
+            Key::Escape if prompt.kind == PromptKind::Filter => {
+                self.mode = InputMode::Normal;
+                effects.refilter = Some(String::new());
+            }
+            Key::Enter => {
+                let kind = prompt.kind;
+                let input = std::mem::take(&mut prompt.input);
+                self.mode = InputMode::Normal;
+                let query = format!("{}{input}", kind.prefix());
+                let matches = match kind {
+                    PromptKind::Search => structure
+                        .index_all(content)
+                        .map(|()| find_matches(content, structure, input.as_bytes())),
+                    PromptKind::Path => resolve_path(content, structure, &input),
+                    PromptKind::Export
+                    | PromptKind::Filter
+                    | PromptKind::Edit
+                    | PromptKind::Sort => {
+                        unreachable!("handled above")
+                    }
+                };
+                self.search = Some(match matches {
+                    Ok(matches) => {
+                        effects.jump_target = match kind {
+                            PromptKind::Search => next_match(structure, &matches, self.selection),
+                            PromptKind::Path
+                            | PromptKind::Export
+                            | PromptKind::Filter
+                            | PromptKind::Edit
+                            | PromptKind::Sort => matches.first().copied(),
+                        };
+                        Search {
+                            query,
+                            matches: Ok(matches),
+                        }
+                    }
+                    Err(err) => Search {
+                        query,
+                        matches: Err(err.to_string()),
+                    },
+                });
+            }
+            Key::Escape => self.mode = InputMode::Normal,
+            Key::Backspace => {
+                let removed = prompt.input.pop();
+                if prompt.kind == PromptKind::Filter {
+                    effects.refilter = Some(prompt.input.clone());
+                }
+                if removed.is_none() {
+                    self.mode = InputMode::Normal;
+                }
+            }
+            Key::Char(ch) => {
+                prompt.input.push(ch);
+                if prompt.kind == PromptKind::Filter {
+                    effects.refilter = Some(prompt.input.clone());
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// `warned` is set if previous key was quit with unsaved edits
+    fn handle_normal_key(
+        &mut self,
+        key: Key,
+        warned: bool,
+        effects: &mut KeyEffects,
+    ) -> Option<Exit> {
+        match (self.pending_prefix.take(), key) {
+            (_, Key::Mouse(mouse)) => self.handle_mouse(mouse),
+            (Some(prefix), key) => self.handle_prefixed_key(prefix, key, effects),
+            (None, Key::Char('q' | 'Q')) if self.patch.is_modified() && !warned => {
+                self.quit_warned = true;
+                self.message = Some("unsaved edits, press again to quit".to_owned());
+            }
+            (None, Key::Char('q')) => return Some(Exit::Quit),
+            (None, Key::Char('Q')) => return Some(Exit::QuitWithoutPrinting),
+            (None, key) => {
+                // navigation keys move cursor of focused pane
+                let handled = match self.panes.focus {
+                    Focus::Data if self.panes.table.is_some() => {
+                        self.handle_table_key(key, effects)
+                    }
+                    Focus::Data => self.handle_data_key(key),
+                    Focus::Overview => false,
+                };
+                if !handled && !self.handle_overview_key(key) {
+                    self.handle_command_key(key, effects);
+                }
+            }
+        }
+        None
+    }
+
+    fn handle_mouse(&mut self, mouse: Mouse) {
+        let height = self.overview_height();
+        let content = &self.content;
+        let structure = &mut self.structure;
+        let panes = &mut self.panes;
+        let overview_width = panes.overview_width(self.width);
+        let in_overview = mouse.column < overview_width;
+        let on_divider = !in_overview && mouse.column < overview_width + Panes::SEPARATOR_WIDTH;
+        let down = mouse.kind == MouseKind::WheelDown;
+        match mouse.kind {
+            MouseKind::Press if mouse.row >= height => {}
+            MouseKind::Press if on_divider => panes.dragging_divider = true,
+            MouseKind::Drag if panes.dragging_divider => {
+                panes.overview_width = mouse.column.min(self.width);
+            }
+            MouseKind::Release => panes.dragging_divider = false,
+            MouseKind::Press if in_overview => {
+                panes.focus = Focus::Overview;
+                match click(structure, self.scroll, height, mouse.row, mouse.column) {
+                    Some(Click::Toggle(index)) => {
+                        if let Err(err) = structure.toggle_expanded(content, index) {
+                            self.message = Some(err.to_string());
+                        }
+                    }
+                    Some(Click::Select(index)) => self.selection = index,
+                    None => {}
+                }
+            }
+            MouseKind::Press if let Some(table) = &mut panes.table => {
+                panes.focus = Focus::Data;
+                let x = mouse.column - overview_width - Panes::SEPARATOR_WIDTH;
+                table.click(mouse.row as usize, x as usize);
+            }
+            MouseKind::Press => {
+                let rows = data_rows(structure, self.selection, panes.data_top, height);
+                if let Some(&line) = rows.get(mouse.row as usize) {
+                    panes.focus = Focus::Data;
+                    panes.data_cursor = line;
+                }
+            }
+            MouseKind::WheelUp | MouseKind::WheelDown if in_overview => {
+                scroll_wheel(
+                    structure,
+                    &mut self.scroll,
+                    &mut self.selection,
+                    height,
+                    down,
+                );
+            }
+            MouseKind::WheelUp | MouseKind::WheelDown if let Some(table) = &mut panes.table => {
+                let lines = WHEEL_LINES as isize;
+                table.move_rows(if down { lines } else { -lines });
+            }
+            MouseKind::WheelUp | MouseKind::WheelDown => {
+                for _ in 0..WHEEL_LINES {
+                    let next = if down {
+                        let rows = data_rows(structure, self.selection, panes.data_top, height + 1);
+                        rows.get(1)
+                            .copied()
+                            .filter(|_| rows.len() > height as usize)
+                    } else {
+                        structure.prev_data_line(self.selection, panes.data_top)
+                    };
+                    let Some(next) = next else {
+                        break;
+                    };
+                    panes.data_top = next;
+                }
+                let rows = data_rows(structure, self.selection, panes.data_top, height);
+                if !rows.contains(&panes.data_cursor)
+                    && let Some(&row) = if down { rows.first() } else { rows.last() }
+                {
+                    panes.data_cursor = row;
+                }
+            }
+            MouseKind::Drag => {}
+        }
+    }
+
+    /// Second key of two key commands, like `zM`
+    fn handle_prefixed_key(&mut self, prefix: char, key: Key, effects: &mut KeyEffects) {
+        let read_only = self.read_only();
+        let content = &self.content;
+        let structure = &mut self.structure;
+        let panes = &mut self.panes;
+        match (prefix, key) {
+            ('z', Key::Char('M')) => {
+                structure.collapse_all();
+            }
+            ('z', Key::Char('R')) => {
+                if let Err(err) = structure.expand_all(content) {
+                    self.message = Some(err.to_string());
+                }
+            }
+            ('z', Key::Char(depth @ '0'..='9')) => {
+                let depth = depth.to_digit(10).unwrap() as usize;
+                if let Err(err) = structure.expand_to_depth(content, depth) {
+                    self.message = Some(err.to_string());
+                }
+            }
+            ('z', Key::Char('h')) => {
+                panes.data_scroll = panes.data_scroll.saturating_sub(1);
+            }
+            ('z', Key::Char('l')) => {
+                panes.data_scroll += 1;
+            }
+            ('z', Key::Char('H')) => {
+                let half = panes.data_width(self.width) as usize / 2;
+                panes.data_scroll = panes.data_scroll.saturating_sub(half.max(1));
+            }
+            ('z', Key::Char('L')) => {
+                let half = panes.data_width(self.width) as usize / 2;
+                panes.data_scroll += half.max(1);
+            }
+            ('m', Key::Char(letter)) if letter.is_ascii_alphabetic() => {
+                self.marks.insert(letter, self.selection);
+                self.message = Some(format!("mark '{letter}' set"));
+            }
+            ('\'', Key::Char(letter)) => match self.marks.get(&letter) {
+                Some(&index) => effects.jump_target = Some(index),
+                None => self.message = Some(format!("mark '{letter}' isn't set")),
+            },
+            ('d', Key::Char('d')) if let Some(read_only) = read_only => {
+                self.message = Some(read_only.to_owned());
+            }
+            ('d', Key::Char('d')) => {
+                let target = panes.focused_node(content, structure, self.selection);
+                effects.edited = Some(self.patch.delete(&mut self.content, structure, target));
+            }
+            _ => {}
+        }
+    }
+
+    /// Navigation keys of data pane, that shows table
+    /// # Returns false if key isn't handled
+    fn handle_table_key(&mut self, key: Key, effects: &mut KeyEffects) -> bool {
+        let height = self.overview_height();
+        let Some(table) = &mut self.panes.table else {
+            return false;
+        };
+        match key {
+            Key::Char('j') | Key::Down => table.move_rows(1),
+            Key::Char('k') | Key::Up => table.move_rows(-1),
+            Key::Char('l') | Key::Right => table.next_column(),
+            Key::Char('h') | Key::Left => table.prev_column(),
+            Key::Char('g') | Key::Home => table.first_row(),
+            Key::Char('G') | Key::End => table.last_row(),
+            Key::PageDown | Key::Ctrl('f') => table.move_rows(height as isize - 1),
+            Key::PageUp | Key::Ctrl('b') => table.move_rows(1 - height as isize),
+            Key::Char('-') => {
+                if let Err(err) = table.hide_column() {
+                    self.message = Some(err.to_string());
+                }
+            }
+            Key::Char('+') => {
+                let shown = table.show_columns();
+                self.message = Some(format!("shown {shown} hidden columns"));
+            }
+            // selects value under cursor in overview, which closes table
+            Key::Enter => {
+                effects.jump_target = Some(table.focused_node(&self.content, &self.structure));
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Navigation keys of data pane, that moves cursor over lines of selection
+    /// # Returns false if key isn't handled
+    fn handle_data_key(&mut self, key: Key) -> bool {
+        let height = self.overview_height();
+        let content = &self.content;
+        let structure = &mut self.structure;
+        let panes = &mut self.panes;
+        match key {
+            Key::Char('j') | Key::Down => {
+                if let Some(line) = structure.next_data_line(self.selection, panes.data_cursor) {
+                    panes.data_cursor = line;
+                }
+            }
+            Key::Char('k') | Key::Up => {
+                if let Some(line) = structure.prev_data_line(self.selection, panes.data_cursor) {
+                    panes.data_cursor = line;
+                }
+            }
+            Key::Char('h') | Key::Left => {
+                let index = panes.data_cursor.index();
+                if index == self.selection {
+                    // root of data pane is always open
+                } else if structure.is_open_in(self.selection, index) {
+                    structure.collapse(index);
+                    panes.data_cursor = DataLine::Open(index);
+                } else if let Some(parent) = structure.parent(index) {
+                    panes.data_cursor = DataLine::Open(parent);
+                }
+            }
+            Key::Char('l') | Key::Right => {
+                if let DataLine::Open(index) = panes.data_cursor {
+                    if structure.is_open_in(self.selection, index) {
+                        panes.data_cursor = DataLine::Open(structure.first_child(index).unwrap());
+                    } else if let Err(err) = structure.set_expanded(content, index, true) {
+                        self.message = Some(err.to_string());
+                    }
+                }
+            }
+            Key::Enter => {
+                let index = panes.data_cursor.index();
+                if index != self.selection {
+                    if let Err(err) = structure.toggle_expanded(content, index) {
+                        self.message = Some(err.to_string());
+                    }
+                    panes.data_cursor = DataLine::Open(index);
+                }
+            }
+            Key::PageDown | Key::Ctrl('f') => {
+                for _ in 0..height {
+                    let Some(line) = structure.next_data_line(self.selection, panes.data_cursor)
+                    else {
+                        break;
+                    };
+                    panes.data_cursor = line;
+                }
+            }
+            Key::PageUp | Key::Ctrl('b') => {
+                for _ in 0..height {
+                    let Some(line) = structure.prev_data_line(self.selection, panes.data_cursor)
+                    else {
+                        break;
+                    };
+                    panes.data_cursor = line;
+                }
+            }
+            Key::Char('g') | Key::Home => {
+                panes.data_cursor = DataLine::Open(self.selection);
+            }
+            Key::Char('G') | Key::End => {
+                panes.data_cursor = structure.last_data_line(self.selection, self.selection);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Navigation keys of overview
+    /// # Returns false if key isn't handled
+    fn handle_overview_key(&mut self, key: Key) -> bool {
+        let height = self.overview_height();
+        let content = &self.content;
+        let structure = &mut self.structure;
+        match key {
+            Key::Char('j') | Key::Down => {
+                if let Some(index) = structure.next_visible(self.selection) {
+                    self.selection = index;
+                }
+            }
+            Key::Char('k') | Key::Up => {
+                if let Some(index) = structure.prev_visible(self.selection) {
+                    self.selection = index;
+                }
+            }
+            Key::Char('h') | Key::Left => {
+                if structure.is_expanded(self.selection)
+                    && structure.first_child(self.selection).is_some()
+                {
+                    structure.collapse(self.selection);
+                } else if let Some(parent) = structure.parent(self.selection) {
+                    self.selection = parent;
+                }
+            }
+            Key::Char('l') | Key::Right => {
+                if !structure.is_expanded(self.selection) {
+                    if let Err(err) = structure.set_expanded(content, self.selection, true) {
+                        self.message = Some(err.to_string());
+                    }
+                } else if let Some(first_child) = structure.first_shown_child(self.selection) {
+                    self.selection = first_child;
+                }
+            }
+            Key::Char('J') => {
+                if let Some(next) = structure.next_shown_sibling(self.selection) {
+                    self.selection = next;
+                }
+            }
+            Key::Char('K') => {
+                if let Some(prev) = structure.prev_shown_sibling(self.selection) {
+                    self.selection = prev;
+                }
+            }
+            Key::PageDown | Key::Ctrl('f') => {
+                for _ in 0..height {
+                    let Some(index) = structure.next_visible(self.selection) else {
+                        break;
+                    };
+                    self.selection = index;
+                }
+            }
+            Key::PageUp | Key::Ctrl('b') => {
+                for _ in 0..height {
+                    let Some(index) = structure.prev_visible(self.selection) else {
+                        break;
+                    };
+                    self.selection = index;
+                }
+            }
+            Key::Char('g') | Key::Home => {
+                self.selection = JsonMetadataIndex::ROOT;
+            }
+            Key::Char('G') | Key::End => {
+                self.selection = structure.last_visible();
+            }
+            Key::Enter => {
+                if let Err(err) = structure.toggle_expanded(content, self.selection) {
+                    self.message = Some(err.to_string());
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Keys of commands, that don't depend on focused pane
+    fn handle_command_key(&mut self, key: Key, effects: &mut KeyEffects) {
+        let read_only = self.read_only();
+        let content = &self.content;
+        let structure = &mut self.structure;
+        let panes = &mut self.panes;
+        match key {
+            Key::Char('e' | 't' | 'D' | 'U') | Key::Ctrl('s')
+                if let Some(read_only) = read_only =>
+            {
+                self.message = Some(read_only.to_owned());
+            }
+            Key::Char(prefix @ ('z' | 'm' | '\'' | 'd')) => self.pending_prefix = Some(prefix),
+            Key::Tab | Key::BackTab => {
+                panes.focus = match panes.focus {
+                    Focus::Overview => Focus::Data,
+                    Focus::Data => Focus::Overview,
+                };
+            }
+            Key::Char(key @ ('y' | 'Y')) => {
+                let target = panes.focused_node(content, structure, self.selection);
+                let text = if key == 'y' {
+                    structure.get(target).source(content).into_owned()
+                } else {
+                    path_to(content, structure, target).into_bytes()
+                };
+                self.message = Some(yank(
+                    &mut self.terminal_output,
+                    self.options.yank_file.as_deref(),
+                    &text,
+                ));
+            }
+            Key::Char('S') => {
+                let other = match self.other_view.take() {
+                    Some(other) => other,
+                    None => match infer_schema(content, structure) {
+                        Ok(schema) => View {
+                            // generated, so it's valid
+                            structure: parse_json_structure_lazy(schema.as_bytes()).unwrap(),
+                            content: Document::from(schema.into_bytes()),
+                            scroll: JsonMetadataIndex::ROOT,
+                            selection: JsonMetadataIndex::ROOT,
+                            marks: BTreeMap::new(),
+                        },
+                        Err(err) => {
+                            self.message = Some(err.to_string());
+                            return;
+                        }
+                    },
+                };
+                self.other_view = Some(View {
+                    content: std::mem::replace(&mut self.content, other.content),
+                    structure: std::mem::replace(structure, other.structure),
+                    scroll: self.scroll,
+                    selection: self.selection,
+                    marks: std::mem::take(&mut self.marks),
+                });
+                self.scroll = other.scroll;
+                self.selection = other.selection;
+                self.marks = other.marks;
+                self.schema_shown = !self.schema_shown;
+                panes.table = None;
+                // matches are indices in the other document
+                self.search = None;
+                structure.clear_filter();
+                panes.data_top = DataLine::Open(self.selection);
+                panes.data_cursor = DataLine::Open(self.selection);
+                panes.data_scroll = 0;
+            }
+            Key::Char('u') => {
+                panes.decode_escapes = !panes.decode_escapes;
+            }
+            Key::Char('i') => {
+                panes.hints = !panes.hints;
+            }
+            Key::Char('T') if panes.table.is_some() => panes.table = None,
+            Key::Char('T') => match Table::new(content, structure, self.selection) {
+                Ok(table) => {
+                    panes.table = Some(table);
+                    panes.focus = Focus::Data;
+                }
+                Err(err) => self.message = Some(err.to_string()),
+            },
+            Key::Char('x') if self.schema_shown => {
+                self.message = Some("schema strings can't be decoded".to_owned());
+            }
+            Key::Char('x') => {
+                let target = panes.focused_node(content, structure, self.selection);
+                effects.edited = Some(self.embedded.toggle(&mut self.content, structure, target));
+            }
+            Key::Char('<') => {
+                panes.overview_width = panes.overview_width(self.width).saturating_sub(2);
+            }
+            Key::Char('>') => {
+                panes.overview_width = (panes.overview_width + 2).min(self.width);
+            }
+            Key::Char('/') => self.mode = InputMode::Prompt(Prompt::new(PromptKind::Search)),
+            Key::Char(':') => self.mode = InputMode::Prompt(Prompt::new(PromptKind::Path)),
+            Key::Char('w') => self.mode = InputMode::Prompt(Prompt::new(PromptKind::Export)),
+            Key::Char('e') => {
+                let target = structure.get(panes.focused_node(content, structure, self.selection));
+                if target.ty.is_scalar() {
+                    let mut prompt = Prompt::new(PromptKind::Edit);
+                    prompt.input = str::from_utf8(&target.source(content)).unwrap().to_owned();
+                    self.mode = InputMode::Prompt(prompt);
+                } else {
+                    self.message =
+                        Some("only strings, numbers, bools and null could be edited".to_owned());
+                }
+            }
+            Key::Char('t') => {
+                let target = panes.focused_node(content, structure, self.selection);
+                effects.edited = Some(self.patch.toggle_bool(&mut self.content, structure, target));
+            }
+            Key::Char('D') => {
+                let target = panes.focused_node(content, structure, self.selection);
+                effects.edited = Some(self.patch.duplicate(&mut self.content, structure, target));
+            }
+            Key::Char('U') => {
+                if self.patch.undo(&mut self.content, structure) {
+                    effects.edited = Some(Ok(()));
+                } else {
+                    self.message = Some("nothing to undo".to_owned());
+                }
+            }
+            Key::Ctrl('s') => {
+                self.message = Some(match &self.options.path {
+                    Some(path) => match write_file_atomically(path, content) {
+                        Ok(()) => {
+                            self.patch.mark_saved();
+                            format!("saved {}", path.display())
+                        }
+                        Err(err) => format!("can't save {}: {err}", path.display()),
+                    },
+                    None => "stdin can't be saved, use 'w' to export".to_owned(),
+                });
+            }
+            Key::Char('o') => self.mode = InputMode::Prompt(Prompt::new(PromptKind::Sort)),
+            // works on children of sorted container too
+            Key::Char('O') => {
+                let parent = structure.parent(self.selection);
+                if !structure.clear_sort(self.selection)
+                    && !parent.is_some_and(|parent| structure.clear_sort(parent))
+                {
+                    self.message = Some("already in document order".to_owned());
+                }
+                if let Some(table) = &mut panes.table {
+                    table.refresh_rows(structure);
+                }
+            }
+            // once, not on every key of filter
+            Key::Char('&') => match structure.index_all(content) {
+                Ok(()) => self.mode = InputMode::Prompt(Prompt::new(PromptKind::Filter)),
+                Err(err) => self.message = Some(err.to_string()),
+            },
+            Key::Char('n') => {
+                if let Some(Search {
+                    matches: Ok(matches),
+                    ..
+                }) = &self.search
+                {
+                    effects.jump_target = next_match(structure, matches, self.selection);
+                }
+            }
+            Key::Char('N') => {
+                if let Some(Search {
+                    matches: Ok(matches),
+                    ..
+                }) = &self.search
+                {
+                    effects.jump_target = prev_match(structure, matches, self.selection);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Applies effects of key and keeps selection and cursors on screen
+    fn settle(&mut self, effects: KeyEffects, previous_selection: JsonMetadataIndex) {
+        let height = self.overview_height();
+        let content = &self.content;
+        let structure = &mut self.structure;
+        let panes = &mut self.panes;
+        let mut jump_target = effects.jump_target;
+        match effects.edited {
+            Some(Ok(())) => {
+                // removed nodes are replaced with their previous siblings or parents
+                self.selection = structure.nearest_attached(self.selection);
+                self.scroll = structure.nearest_attached(self.scroll);
+                self.marks
+                    .retain(|_, &mut index| structure.is_attached(index));
+                for line in [&mut panes.data_top, &mut panes.data_cursor] {
+                    if !structure.is_attached(line.index()) {
+                        *line = DataLine::Open(structure.nearest_attached(line.index()));
+                    }
+                }
+                // matches and schema are of previous content
+                structure.clear_filter();
+                panes.table = panes
+                    .table
+                    .take()
+                    .and_then(|table| table.reopen(content, structure).ok());
+                self.search = None;
+                self.other_view = None;
+            }
+            Some(Err(err)) => self.message = Some(err.to_string()),
+            None => {}
+        }
+        if let Some(input) = effects.refilter {
+            if input.is_empty() {
+                structure.clear_filter();
+                self.search = None;
+            } else {
+                let previous = self.search.as_ref().and_then(|search| {
+                    let previous = search.query.strip_prefix(PromptKind::Filter.prefix())?;
+                    search
+                        .matches
+                        .as_ref()
+                        .ok()
+                        .filter(|_| input.starts_with(previous))
+                });
+                let matches = match previous {
+                    Some(previous) => {
+                        narrow_matches(content, structure, previous, input.as_bytes())
+                    }
+                    None => find_matches(content, structure, input.as_bytes()),
+                };
+                structure.set_filter(&matches);
+                if !structure.is_shown(self.selection) {
+                    jump_target = matches.first().copied();
+                }
+                self.search = Some(Search {
+                    query: format!("{}{input}", PromptKind::Filter.prefix()),
+                    matches: Ok(matches),
+                });
+            }
+            if let Some(table) = &mut panes.table {
+                table.refresh_rows(structure);
+            }
+        }
+        if let Some(target) = jump_target {
+            structure.expand_ancestors(target);
+            self.selection = target;
+            self.scroll = center_on(structure, self.selection, height);
+        }
+        // collapsing could hide them
+        self.selection = structure.nearest_visible(self.selection);
+        if panes
+            .table
+            .as_ref()
+            .is_some_and(|table| table.array() != self.selection)
+        {
+            panes.table = None;
+        }
+        let data_width = panes.data_width(self.width) as usize;
+        if let Some(table) = &mut panes.table
+            && let Err(err) =
+                table.scroll_to_cursor(content, structure, data_width, height as usize)
+        {
+            self.message = Some(err.to_string());
+        }
+        // edits could replace selection with container, that isn't indexed
+        if let Err(err) = structure.index_children(content, self.selection) {
+            self.message = Some(err.to_string());
+        }
+        if self.selection != previous_selection {
+            panes.data_top = DataLine::Open(self.selection);
+            panes.data_cursor = DataLine::Open(self.selection);
+            panes.data_scroll = 0;
+        }
+        self.scroll = structure.nearest_visible(self.scroll);
+        self.scroll = scroll_to_selection(structure, self.scroll, self.selection, height);
+        panes.data_cursor = structure.nearest_data_line(self.selection, panes.data_cursor);
+        panes.data_top = structure.nearest_data_line(self.selection, panes.data_top);
+        panes.data_top = scroll_to_data_cursor(
+            structure,
+            self.selection,
+            panes.data_top,
+            panes.data_cursor,
+            height,
+        );
+    }
+}
+
+impl Interactive for Viewer {
+    /// # Returns `height` lines, the last one is status line
+    fn render(&mut self) -> Vec<String> {
+        let status_line = match &self.mode {
+            InputMode::Prompt(prompt) => prompt.to_string(),
+            InputMode::Normal if let Some(message) = &self.message => message.clone(),
+            InputMode::Normal => {
+                let path = path_to(&self.content, &self.structure, self.selection);
+                let path = if self.panes.table.is_some() {
+                    format!("[table] {path}")
+                } else {
+                    path
+                };
+                let path = if self.schema_shown {
+                    format!("[schema] {path}")
+                } else {
+                    path
+                };
+                let path = if self.structure.is_filtered() {
+                    format!("[filtered] {path}")
+                } else {
+                    path
+                };
+                let parent = self.structure.parent(self.selection);
+                let path = if self.structure.is_sorted(self.selection)
+                    || parent.is_some_and(|parent| self.structure.is_sorted(parent))
+                {
+                    format!("[sorted] {path}")
+                } else {
+                    path
+                };
+                let path = if self.patch.is_modified() {
+                    format!("[modified] {path}")
+                } else {
+                    path
+                };
+                let path = if self.embedded.is_empty() {
+                    path
+                } else {
+                    format!("[decoded] {path}")
+                };
+                match &self.search {
+                    Some(search) => {
+                        format!(
+                            "{path}    {}",
+                            search.status(&self.structure, self.selection)
+                        )
+                    }
+                    None => path,
+                }
+            }
+        };
+        let height = self.overview_height();
+        render_frame(
+            &self.content,
+            &mut self.structure,
+            (self.width, height),
+            &self.panes,
+            self.scroll,
+            self.selection,
+            &sanitize(&status_line),
+        )
+    }
+
+    fn handle_key(&mut self, key: Key) -> Option<Exit> {
+        self.message = None;
+        let previous_selection = self.selection;
+        let warned = std::mem::take(&mut self.quit_warned);
+        let mut effects = KeyEffects::default();
+        match self.mode {
+            InputMode::Prompt(_) => self.handle_prompt_key(key, &mut effects),
+            InputMode::Normal => {
+                let exit = self.handle_normal_key(key, warned, &mut effects);
+                if exit.is_some() {
+                    return exit;
+                }
+            }
+        }
+        self.settle(effects, previous_selection);
+        None
+    }
+
+    fn resize(&mut self, width: u16, height: u16) {
+        self.panes.resize(self.width, width);
+        self.width = width;
+        self.height = height;
+        let data_width = self.panes.data_width(width) as usize;
+        let overview_height = self.overview_height() as usize;
+        if let Some(table) = &mut self.panes.table
+            && let Err(err) = table.scroll_to_cursor(
+                &self.content,
+                &mut self.structure,
+                data_width,
+                overview_height,
+            )
+        {
+            self.message = Some(err.to_string());
+        }
+    }
+
+    /// Escape sequences for terminal, written since last call
+    fn take_terminal_output(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.terminal_output)
+    }
+}
+
+/// Results of key, that are applied by `Viewer::settle` after key is handled in its mode
+#[derive(Default)]
+struct KeyEffects {
+    jump_target: Option<JsonMetadataIndex>,
+    /// Result of edit, undo or decoding
+    edited: Option<anyhow::Result<()>>,
+    /// New query of live filter
+    refilter: Option<String>,
+}
+
+/// State of document, that isn't shown now
+struct View {
+    content: Document,
+    structure: JsonMetadata,
+    scroll: JsonMetadataIndex,
+    selection: JsonMetadataIndex,
+    marks: BTreeMap<char, JsonMetadataIndex>,
+}
+
+enum InputMode {
+    Normal,
+    Prompt(Prompt),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PromptKind {
+    Search,
+    Path,
+    /// File name to write focused node to
+    Export,
+    /// Hides nodes, that don't match, while typing
+    Filter,
+    /// New json value of focused scalar
+    Edit,
+    /// Path of values, that children of selection are ordered by
+    Sort,
+}
+impl PromptKind {
+    fn prefix(self) -> &'static str {
+        match self {
+            PromptKind::Search => "/",
+            PromptKind::Path => ":",
+            PromptKind::Export => "write to: ",
+            PromptKind::Filter => "&",
+            PromptKind::Edit => "edit: ",
+            PromptKind::Sort => "sort by: ",
+        }
+    }
+}
+
+struct Prompt {
+    kind: PromptKind,
+    input: String,
+}
+impl Prompt {
+    fn new(kind: PromptKind) -> Self {
+        Prompt {
+            kind,
+            input: String::new(),
+        }
+    }
+}
+impl std::fmt::Display for Prompt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", self.kind.prefix(), self.input)
+    }
+}
+
+/// Result of last search or path query, used by `n`/`N`
+struct Search {
+    query: String,
+    /// Error message if query is invalid
+    matches: Result<Vec<JsonMetadataIndex>, String>,
+}
+impl Search {
+    fn status(&self, structure: &JsonMetadata, selection: JsonMetadataIndex) -> String {
+        let query = &self.query;
+        let matches = match &self.matches {
+            Ok(matches) => matches,
+            Err(err) => return format!("{query}: {err}"),
+        };
+        if matches.is_empty() {
+            return format!("{query} [no matches]");
+        }
+        let current = match matches
+            .binary_search_by_key(&structure.source_start(selection), |&index| {
+                structure.source_start(index)
+            }) {
+            Ok(position) => (position + 1).to_string(),
+            Err(_) => "?".to_owned(),
+        };
+        format!("{query} [{current}/{}]", matches.len())
+    }
+}
+
+/// Puts `text` into `yank_file` or terminal clipboard
+/// # Returns status message
+fn yank(output: &mut Vec<u8>, yank_file: Option<&Path>, text: &[u8]) -> String {
+    match yank_file {
+        Some(path) => match std::fs::write(path, text) {
+            Ok(()) => format!("yanked {} bytes to {}", text.len(), path.display()),
+            Err(err) => format!("can't write {}: {err}", path.display()),
+        },
+        None => {
+            output.extend_from_slice(osc52_copy(text).as_bytes());
+            format!("copied {} bytes to clipboard", text.len())
+        }
+    }
+}
+
+/// # Returns up to `count` lines of data pane, that starts at `top`
+fn data_rows(
+    structure: &JsonMetadata,
+    root: JsonMetadataIndex,
+    top: DataLine,
+    count: u16,
+) -> Vec<DataLine> {
+    std::iter::successors(Some(top), |&line| structure.next_data_line(root, line))
+        .take(count as usize)
+        .collect()
+}
+
+/// # Returns top line of data pane closest to `top`, that shows cursor
+fn scroll_to_data_cursor(
+    structure: &JsonMetadata,
+    root: JsonMetadataIndex,
+    top: DataLine,
+    cursor: DataLine,
+    height: u16,
+) -> DataLine {
+    if cursor.position(structure) < top.position(structure) || cursor == top {
+        return cursor;
+    }
+    let mut current = top;
+    for _ in 1..height {
+        match structure.next_data_line(root, current) {
+            Some(next) if next == cursor => return top,
+            Some(next) => current = next,
+            None => break,
+        }
+    }
+    let mut top = cursor;
+    for _ in 1..height {
+        let Some(prev) = structure.prev_data_line(root, top) else {
+            break;
+        };
+        top = prev;
+    }
+    top
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Overview,
+    Data,
+}
+
+/// Pane layout, view state of data pane and display options
+struct Panes {
+    overview_width: u16,
+    /// Pane, that receives navigation keys, switched with Tab
+    focus: Focus,
+    /// First line of data pane
+    data_top: DataLine,
+    data_cursor: DataLine,
+    /// Horizontal scroll of data pane
+    data_scroll: usize,
+    /// Show keys and strings with JSON escapes decoded
+    decode_escapes: bool,
+    /// Show annotations of values, like dates of timestamps
+    hints: bool,
+    /// Separator is dragged with mouse
+    dragging_divider: bool,
+    /// Selected array of objects shown as table instead of json
+    table: Option<Table>,
+    theme: Theme,
+}
+impl Panes {
+    const SEPARATOR: &str = " | ";
+    const SEPARATOR_WIDTH: u16 = 3;
+
+    fn new(screen_width: u16, theme: Theme) -> Self {
+        Panes {
+            overview_width: screen_width * 2 / 5,
+            focus: Focus::Overview,
+            data_top: DataLine::Open(JsonMetadataIndex::ROOT),
+            data_cursor: DataLine::Open(JsonMetadataIndex::ROOT),
+            data_scroll: 0,
+            decode_escapes: false,
+            hints: true,
+            dragging_divider: false,
+            table: None,
+            theme,
+        }
+    }
+    /// Keeps proportions
+    fn resize(&mut self, old_screen_width: u16, new_screen_width: u16) {
+        self.overview_width = (self.overview_width as u32 * new_screen_width as u32
+            / old_screen_width.max(1) as u32) as u16;
+    }
+    /// Node under cursor of focused pane
+    fn focused_node(
+        &self,
+        content: &Document,
+        structure: &JsonMetadata,
+        selection: JsonMetadataIndex,
+    ) -> JsonMetadataIndex {
+        match self.focus {
+            Focus::Overview => selection,
+            Focus::Data if let Some(table) = &self.table => table.focused_node(content, structure),
+            Focus::Data => self.data_cursor.index(),
+        }
+    }
+    fn overview_width(&self, screen_width: u16) -> u16 {
+        self.overview_width.min(screen_width)
+    }
+    fn data_width(&self, screen_width: u16) -> u16 {
+        screen_width.saturating_sub(self.overview_width(screen_width) + Self::SEPARATOR_WIDTH)
+    }
+}
+
+fn render_frame(
+    content: &Document,
+    structure: &mut JsonMetadata,
+    (width, height): (u16, u16),
+    panes: &Panes,
+    scroll: JsonMetadataIndex,
+    selection: JsonMetadataIndex,
+    status_line: &str,
+) -> Vec<String> {
+    let overview_width = panes.overview_width(width) as usize;
+    let data_width = panes.data_width(width) as usize;
+
+    let overview_lines = render_overview(
+        content,
+        structure,
+        scroll,
+        height as usize,
+        selection,
+        panes,
+    );
+    let data_lines = match &panes.table {
+        Some(table) => table.render(
+            content,
+            structure,
+            (data_width, height as usize),
+            &panes.theme,
+            panes.decode_escapes,
+            panes.focus == Focus::Data,
+        ),
+        None => render_data(
+            content,
+            structure,
+            selection,
+            panes.data_top,
+            height as usize,
+            (panes.focus == Focus::Data).then_some(panes.data_cursor),
+            panes,
+        ),
+    };
+    let mut lines = Vec::with_capacity(height as usize + 1);
+    for i in 0..height as usize {
+        let overview_line = overview_lines.get(i).map_or("", String::as_str);
+        let data_line = data_lines.get(i).map_or("", String::as_str);
+        lines.push(format!(
+            "{}{RESET}{}{}{RESET}",
+            fit_to_width(overview_line, 0, overview_width),
+            &Panes::SEPARATOR[..Panes::SEPARATOR.len().min(width as usize - overview_width)],
+            fit_to_width(data_line, panes.data_scroll, data_width),
+        ));
+    }
+    lines.push(fit_to_width(status_line, 0, width as usize));
+    lines
+}
+
+/// Styles of selected line are dropped, it has only selection style
+fn unless_selected(selected: bool, style: &str) -> &str {
+    if selected { "" } else { style }
+}
+
+fn render_overview(
+    content: &Document,
+    structure: &JsonMetadata,
+    start: JsonMetadataIndex,
+    lines_needed: usize,
+    selection: JsonMetadataIndex,
+    panes: &Panes,
+) -> Vec<String> {
+    let decode_escapes = panes.decode_escapes;
+    let theme = &panes.theme;
+    let mut lines = Vec::new();
+    let mut current_ix = start;
+    let mut indentation = structure.depth(start);
+    'outer: while lines.len() < lines_needed {
+        let current = structure.get(current_ix);
+        let prefix = match current.name_or_index {
+            NameOrIndex::Name { .. } => display_text(
+                str::from_utf8(current.name(content).unwrap()).unwrap(),
+                decode_escapes,
+            )
+            .into_owned(),
+            NameOrIndex::Index(index) => format!("{index}"),
+            // shown like first element
+            NameOrIndex::Root => "0".to_owned(),
+        };
+        let current_line_selected = current_ix == selection;
+        let style = |style| unless_selected(current_line_selected, style);
+        let indentation_text = match current.ty {
+            ObjectType::Array | ObjectType::Structure if indentation > 0 => format!(
+                "{}{} ",
+                "  ".repeat(indentation - 1),
+                if current.expanded { '-' } else { '+' }
+            ),
+            _ => "  ".repeat(indentation),
+        };
+        let prefix = format!(
+            "{selection}{indentation_text}{prefix} ",
+            selection = if current_line_selected {
+                &theme.selection
+            } else {
+                RESET
+            },
+        );
+        match current.ty {
+            ObjectType::Array => {
+                lines.push(format!("{prefix}{}arr", style(&theme.container)));
+                if current.expanded
+                    && let Some(first_child) = structure.first_shown_child(current_ix)
+                {
+                    current_ix = first_child;
+                    indentation += 1;
+                    continue;
+                }
+            }
+            ObjectType::Structure => {
+                lines.push(format!("{prefix}{}obj", style(&theme.container)));
+                if current.expanded
+                    && let Some(first_child) = structure.first_shown_child(current_ix)
+                {
+                    current_ix = first_child;
+                    indentation += 1;
+                    continue;
+                }
+            }
+            ObjectType::EmptyArray => {
+                lines.push(format!("{prefix}= []"));
+            }
+            ObjectType::EmptyStructure => {
+                lines.push(format!("{prefix}= {{}}"));
+            }
+            ObjectType::String => {
+                lines.push(format!(
+                    "{prefix}{}{}",
+                    style(&theme.string),
+                    display_text(
+                        str::from_utf8(&current.source(content)).unwrap(),
+                        decode_escapes
+                    )
+                ));
+            }
+            ObjectType::Null => {
+                lines.push(format!("{prefix}{}null", style(&theme.null)));
+            }
+            ObjectType::Number => {
+                lines.push(format!(
+                    "{prefix}{}{}",
+                    style(&theme.number),
+                    display_text(
+                        str::from_utf8(&current.source(content)).unwrap(),
+                        decode_escapes
+                    )
+                ));
+            }
+            ObjectType::Bool => {
+                lines.push(format!(
+                    "{prefix}{}{}",
+                    style(&theme.bool),
+                    display_text(
+                        str::from_utf8(&current.source(content)).unwrap(),
+                        decode_escapes
+                    )
+                ));
+            }
+        }
+        loop {
+            if let Some(next) = structure.next_shown_sibling(current_ix) {
+                current_ix = next;
+                break;
+            } else if let Some(parent) = structure.parent(current_ix) {
+                indentation -= 1;
+                current_ix = parent;
+            } else {
+                break 'outer;
+            }
+        }
+    }
+    lines
+}
+
+fn render_data(
+    content: &Document,
+    structure: &JsonMetadata,
+    root_ix: JsonMetadataIndex,
+    top: DataLine,
+    lines_needed: usize,
+    cursor: Option<DataLine>,
+    panes: &Panes,
+) -> Vec<String> {
+    let decode_escapes = panes.decode_escapes;
+    let theme = &panes.theme;
+    let mut lines = Vec::new();
+    let root_depth = structure.depth(root_ix);
+    let mut line = top;
+    while lines.len() < lines_needed {
+        let current_ix = line.index();
+        let current = structure.get(current_ix);
+        let selected = cursor == Some(line);
+        let style = |style| unless_selected(selected, style);
+        let comma = if current.next.is_some() && current_ix != root_ix {
+            ","
+        } else {
+            ""
+        };
+        let indentation = "  ".repeat(structure.depth(current_ix) - root_depth);
+        let text = match line {
+            DataLine::Open(_) => {
+                let prefix = if current_ix == root_ix {
+                    String::new()
+                } else if let NameOrIndex::Name { start, len } = current.name_or_index {
+                    format!(
+                        "{}{}{}: ",
+                        style(&theme.key),
+                        display_text(
+                            str::from_utf8(content.token(start..start + len.get() as usize))
+                                .unwrap(),
+                            decode_escapes
+                        ),
+                        style(RESET),
+                    )
+                } else {
+                    String::new()
+                };
+                let open = structure.is_open_in(root_ix, current_ix);
+                let (styles, value) = match current.ty {
+                    ObjectType::Array if open => ("", Cow::Borrowed("[")),
+                    ObjectType::Structure if open => ("", Cow::Borrowed("{")),
+                    ObjectType::Array => ("", Cow::Borrowed("[…]")),
+                    ObjectType::Structure => ("", Cow::Borrowed("{…}")),
+                    ObjectType::EmptyArray => ("", Cow::Borrowed("[]")),
+                    ObjectType::EmptyStructure => ("", Cow::Borrowed("{}")),
+                    ty => (
+                        match ty {
+                            ObjectType::String => theme.string.as_str(),
+                            ObjectType::Number => &theme.number,
+                            ObjectType::Null => &theme.null,
+                            _ => &theme.bool,
+                        },
+                        display_text(
+                            str::from_utf8(&current.source(content)).unwrap(),
+                            decode_escapes,
+                        )
+                        .into_owned()
+                        .into(),
+                    ),
+                };
+                let styles = style(styles);
+                let comma = if open { "" } else { comma };
+                let hint = match panes.hints && current.ty.is_scalar() {
+                    true => value_hint(current.ty, &current.source(content)),
+                    false => None,
+                };
+                let hint = match hint {
+                    Some(hint) => format!(
+                        "  {}// {}{}",
+                        style(&theme.hint),
+                        sanitize(&hint),
+                        style(RESET)
+                    ),
+                    None => String::new(),
+                };
+                format!("{prefix}{styles}{value}{}{comma}{hint}", style(RESET))
+            }
+            DataLine::Close(_) => match current.ty {
+                ObjectType::Array => format!("]{comma}"),
+                _ => format!("}}{comma}"),
+            },
+        };
+        let selection = if selected {
+            theme.selection.as_str()
+        } else {
+            ""
+        };
+        lines.push(format!("{selection}{indentation}{text}"));
+        match structure.next_data_line(root_ix, line) {
+            Some(next) => line = next,
+            None => break,
+        }
+    }
+    lines
+}
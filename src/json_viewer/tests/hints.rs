@@ -0,0 +1,76 @@
+//! Annotations of values and decoding of json inside of strings
+
+use json_viewer::*;
+
+fn number(source: &str) -> Option<String> {
+    value_hint(ObjectType::Number, source.as_bytes())
+}
+
+fn string(source: &str) -> Option<String> {
+    value_hint(ObjectType::String, format!("\"{source}\"").as_bytes())
+}
+
+#[test]
+fn timestamps_are_shown_as_dates() {
+    assert_eq!(number("1700000000").unwrap(), "2023-11-14T22:13:20Z");
+    assert_eq!(number("1700000000123").unwrap(), "2023-11-14T22:13:20.123Z");
+    assert_eq!(number("951782400").unwrap(), "951,782,400");
+    assert_eq!(number("1234"), None);
+}
+
+#[test]
+fn large_numbers_get_separators() {
+    assert_eq!(number("-12345.5").unwrap(), "-12,345.5");
+    assert_eq!(number("123456789012").unwrap(), "123,456,789,012");
+    assert_eq!(
+        number("9007199254740993").unwrap(),
+        "9,007,199,254,740,993, not exact as f64"
+    );
+}
+
+#[test]
+fn exponent_isnt_grouped() {
+    assert_eq!(number("12345e3").unwrap(), "12,345e3");
+    assert_eq!(number("-12345.25E-10").unwrap(), "-12,345.25E-10");
+    assert_eq!(number("1700000000e3").unwrap(), "1,700,000,000e3");
+    assert_eq!(
+        number("9007199254740993e0").unwrap(),
+        "9,007,199,254,740,993e0, not exact as f64"
+    );
+    assert_eq!(number("1234e10"), None);
+    assert_eq!(number("1e12345"), None);
+}
+
+#[test]
+fn base64_is_decoded_if_readable() {
+    assert_eq!(string("aGVsbG8gd29ybGQ=").unwrap(), "base64: hello world");
+    assert_eq!(string("aGVsbG8gd29ybGQ").unwrap(), "base64: hello world");
+    assert_eq!(string("iVBORw0KGgo=").unwrap(), "base64: png, 8 bytes");
+    assert_eq!(string("username"), None);
+    assert_eq!(string("not base64!"), None);
+}
+
+#[test]
+fn strings_with_json_are_detected() {
+    assert_eq!(
+        string(r#"{\"a\": [1, true]}"#).unwrap(),
+        "json, 'x' decodes it"
+    );
+    assert_eq!(
+        decode_embedded_json(br#"" [ 1 , {\"b\" : null} ] ""#).unwrap(),
+        br#"[1,{"b":null}]"#
+    );
+    assert_eq!(
+        decode_embedded_json(br#""[1e5, -2.5E-3]""#).unwrap(),
+        b"[1e5,-2.5E-3]"
+    );
+    for invalid in [
+        r#""{\"a\": }""#,
+        r#""[1, 2] tail""#,
+        r#""[tru]""#,
+        r#""\"text\"""#,
+        r#""42""#,
+    ] {
+        assert_eq!(decode_embedded_json(invalid.as_bytes()), None, "{invalid}");
+    }
+}
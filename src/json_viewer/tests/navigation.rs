@@ -0,0 +1,423 @@
+//! Overview navigation is checked against naive pre-order traversal of random documents,
+//! nodes updated by edits against parsing of edited document
+
+use json_viewer::*;
+use proptest::{prelude::*, test_runner::TestCaseError};
+
+#[derive(Debug, Clone)]
+enum Value {
+    Null,
+    Bool(bool),
+    /// Source of number, with optional fraction and exponent
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    /// Keys are generated from positions, so they are unique
+    Object(Vec<Value>),
+}
+
+impl Value {
+    fn write(&self, output: &mut String) {
+        match self {
+            Value::Null => output.push_str("null"),
+            Value::Bool(value) => output.push_str(&value.to_string()),
+            Value::Number(value) => output.push_str(value),
+            Value::String(value) => output.push_str(&format!("\"{value}\"")),
+            Value::Array(items) => {
+                output.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        output.push_str(", ");
+                    }
+                    item.write(output);
+                }
+                output.push(']');
+            }
+            Value::Object(members) => {
+                output.push('{');
+                for (i, member) in members.iter().enumerate() {
+                    if i > 0 {
+                        output.push(',');
+                    }
+                    output.push_str(&format!("\n\"k{i}\": "));
+                    member.write(output);
+                }
+                output.push('}');
+            }
+        }
+    }
+}
+
+fn value() -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        "-?(0|[1-9][0-9]{0,5})(\\.[0-9]{1,3})?([eE][+-]?[0-9]{1,2})?".prop_map(Value::Number),
+        "[a-z ]{0,6}".prop_map(Value::String),
+    ];
+    leaf.prop_recursive(5, 64, 6, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..6).prop_map(Value::Array),
+            prop::collection::vec(inner, 0..6).prop_map(Value::Object),
+        ]
+    })
+}
+
+/// Document with random expansion of containers, sorting and optional filter
+#[derive(Debug)]
+struct Case {
+    content: String,
+    expanded: Vec<bool>,
+    /// Containers, which children are sorted by name or value, descending if `Some(true)`
+    sorted: Vec<Option<bool>>,
+    /// Nodes, that match filter
+    matches: Option<Vec<bool>>,
+}
+
+fn case() -> impl Strategy<Value = Case> {
+    value().prop_flat_map(|value| {
+        // top level is a container, like most documents
+        let mut content = String::new();
+        Value::Array(vec![value]).write(&mut content);
+        let nodes = parse_json_structure(content.as_bytes()).unwrap().len();
+        (
+            Just(content),
+            prop::collection::vec(any::<bool>(), nodes),
+            prop::collection::vec(prop::option::weighted(0.3, any::<bool>()), nodes),
+            prop::option::of(prop::collection::vec(prop::bool::weighted(0.2), nodes)),
+        )
+            .prop_map(|(content, expanded, sorted, matches)| Case {
+                content,
+                expanded,
+                sorted,
+                matches,
+            })
+    })
+}
+
+fn structure(case: &Case) -> JsonMetadata {
+    let content = Document::from(case.content.clone().into_bytes());
+    let mut structure = parse_json_structure_lazy(case.content.as_bytes()).unwrap();
+    structure.index_all(&content).unwrap();
+    for (index, &expanded) in case.expanded.iter().enumerate() {
+        structure
+            .set_expanded(&content, JsonMetadataIndex::new(index), expanded)
+            .unwrap();
+    }
+    for (index, &descending) in case.sorted.iter().enumerate() {
+        if let Some(descending) = descending {
+            // scalars can't be sorted
+            let _ =
+                structure.sort_children(&content, JsonMetadataIndex::new(index), "", descending);
+        }
+    }
+    if let Some(matches) = &case.matches {
+        let matches: Vec<_> = (0..structure.len())
+            .map(JsonMetadataIndex::new)
+            .filter(|index| matches[index.get()])
+            .collect();
+        structure.set_filter(&matches);
+    }
+    structure
+}
+
+/// Type, name and source of node
+type Node = (ObjectType, Option<Vec<u8>>, Vec<u8>);
+
+/// Nodes in document order, without nodes removed by edits
+fn nodes(content: &Document, structure: &JsonMetadata) -> Vec<Node> {
+    std::iter::once(JsonMetadataIndex::ROOT)
+        .chain(structure.descendants(JsonMetadataIndex::ROOT))
+        .map(|index| {
+            let object = structure.get(index);
+            let name = object.name(content).map(<[u8]>::to_vec);
+            (object.ty, name, object.source(content).into_owned())
+        })
+        .collect()
+}
+
+/// Edit of node, that is picked from attached nodes by position modulo their number
+#[derive(Debug, Clone)]
+enum Edit {
+    Replace(usize, &'static str),
+    Delete(usize),
+    Duplicate(usize),
+    Undo,
+}
+
+fn edit() -> impl Strategy<Value = Edit> {
+    let value = prop::sample::select(vec!["null", "true", "-1.5e3", "12", "\"a b\"", "\"\""]);
+    prop_oneof![
+        (any::<usize>(), value).prop_map(|(node, value)| Edit::Replace(node, value)),
+        any::<usize>().prop_map(Edit::Delete),
+        any::<usize>().prop_map(Edit::Duplicate),
+        Just(Edit::Undo),
+    ]
+}
+
+/// Nodes, that are indexed and weren't removed by edits
+fn attached(structure: &JsonMetadata) -> Vec<JsonMetadataIndex> {
+    std::iter::once(JsonMetadataIndex::ROOT)
+        .chain(structure.descendants(JsonMetadataIndex::ROOT))
+        .collect()
+}
+
+/// Nodes and array indices are the same as after parsing of edited document
+fn check_like_parsed(
+    content: &Document,
+    structure: &mut JsonMetadata,
+) -> Result<(), TestCaseError> {
+    structure.index_all(content).unwrap();
+    let text = content.to_vec();
+    let parsed = parse_json_structure(&text).unwrap();
+    let indices = |structure: &JsonMetadata| -> Vec<_> {
+        attached(structure)
+            .into_iter()
+            .map(|index| match structure.get(index).name_or_index {
+                NameOrIndex::Index(position) => Some(position),
+                NameOrIndex::Name { .. } | NameOrIndex::Root => None,
+            })
+            .collect()
+    };
+    prop_assert_eq!(
+        nodes(content, structure),
+        nodes(&Document::from(text.clone()), &parsed)
+    );
+    prop_assert_eq!(indices(structure), indices(&parsed));
+    Ok(())
+}
+
+/// Whitespace around brackets, colons and commas; generated strings have no quotes inside
+fn spaced(content: &str) -> String {
+    let mut output = String::new();
+    let mut in_string = false;
+    for ch in content.chars() {
+        in_string ^= ch == '"';
+        match ch {
+            '[' | ']' | '{' | '}' | ':' | ',' if !in_string => {
+                output.push_str(&format!(" \n{ch}\t "))
+            }
+            _ => output.push(ch),
+        }
+    }
+    output
+}
+
+/// Rows of overview
+fn naive_visible(structure: &JsonMetadata) -> Vec<JsonMetadataIndex> {
+    fn visit(
+        structure: &JsonMetadata,
+        index: JsonMetadataIndex,
+        rows: &mut Vec<JsonMetadataIndex>,
+    ) {
+        rows.push(index);
+        if structure.is_expanded(index) {
+            for child in structure.displayed_children(index) {
+                if structure.is_shown(child) {
+                    visit(structure, child, rows);
+                }
+            }
+        }
+    }
+    let mut rows = Vec::new();
+    visit(structure, JsonMetadataIndex::ROOT, &mut rows);
+    rows
+}
+
+proptest! {
+    #[test]
+    fn indexing_tolerates_whitespace_and_streams(case in case()) {
+        let compact = Document::from(case.content.clone().into_bytes());
+        let compact = nodes(&compact, &parse_json_structure(case.content.as_bytes()).unwrap());
+        let spaced = spaced(&case.content);
+        let stream = format!("{spaced}\n{spaced}\n");
+        for text in [&spaced, &stream] {
+            let content = Document::from(text.clone().into_bytes());
+            let full = nodes(&content, &parse_json_structure(text.as_bytes()).unwrap());
+            let mut lazy = parse_json_structure_lazy(text.as_bytes()).unwrap();
+            lazy.index_all(&content).unwrap();
+            prop_assert_eq!(&nodes(&content, &lazy), &full);
+            let names = |nodes: &[Node]| -> Vec<_> {
+                nodes.iter().map(|(ty, name, _)| (*ty, name.clone())).collect()
+            };
+            let documents = if text == &stream { 2 } else { 1 };
+            let expected: Vec<_> = std::iter::repeat_n(names(&compact), documents).flatten().collect();
+            // stream root spans all documents
+            prop_assert_eq!(&names(&full)[documents - 1..], &expected[..]);
+        }
+    }
+
+    #[test]
+    fn next_visible_walks_rows_in_order(case in case()) {
+        let structure = structure(&case);
+        let rows = naive_visible(&structure);
+        let walked: Vec<_> =
+            std::iter::successors(Some(JsonMetadataIndex::ROOT), |&index| structure.next_visible(index))
+                .collect();
+        prop_assert_eq!(walked, rows);
+    }
+
+    #[test]
+    fn prev_visible_is_inverse_of_next_visible(case in case()) {
+        let structure = structure(&case);
+        let rows = naive_visible(&structure);
+        prop_assert_eq!(structure.prev_visible(JsonMetadataIndex::ROOT), None);
+        for pair in rows.windows(2) {
+            prop_assert_eq!(structure.prev_visible(pair[1]), Some(pair[0]));
+            prop_assert_eq!(structure.next_visible(pair[0]), Some(pair[1]));
+        }
+    }
+
+    #[test]
+    fn rows_are_displayed_in_order(case in case()) {
+        let structure = structure(&case);
+        let rows = naive_visible(&structure);
+        for pair in rows.windows(2) {
+            prop_assert!(structure.displayed_before(pair[0], pair[1]));
+            prop_assert!(!structure.displayed_before(pair[1], pair[0]));
+        }
+    }
+
+    #[test]
+    fn sorting_reorders_children_until_cleared(case in case()) {
+        let mut structure = structure(&case);
+        let content = &Document::from(case.content.clone().into_bytes());
+        for index in (0..structure.len()).map(JsonMetadataIndex::new) {
+            let children: Vec<_> = structure.children(index).collect();
+            let mut displayed: Vec<_> = structure.displayed_children(index).collect();
+            if structure.is_sorted(index) {
+                let values: Vec<_> = displayed
+                    .iter()
+                    .map(|&child| {
+                        let object = structure.get(child);
+                        match object.name(content) {
+                            Some(name) => name.to_vec(),
+                            // strings are compared without quotes
+                            None if object.ty == ObjectType::String => {
+                                let source = object.source(content);
+                                source[1..source.len() - 1].to_vec()
+                            }
+                            None => object.source(content).into_owned(),
+                        }
+                    })
+                    .collect();
+                // names and strings of generated documents have no escapes
+                if structure.ty(index) == ObjectType::Structure
+                    || displayed.iter().all(|&child| structure.ty(child) == ObjectType::String)
+                {
+                    let ascending = values.windows(2).all(|pair| pair[0] <= pair[1]);
+                    let descending = values.windows(2).all(|pair| pair[0] >= pair[1]);
+                    prop_assert!(ascending || descending);
+                }
+                prop_assert!(structure.clear_sort(index));
+                prop_assert_eq!(structure.displayed_children(index).collect::<Vec<_>>(), children.clone());
+            }
+            displayed.sort_unstable_by_key(|&child| structure.source_start(child));
+            prop_assert_eq!(displayed, children);
+        }
+    }
+
+    #[test]
+    fn last_visible_is_last_row(case in case()) {
+        let structure = structure(&case);
+        let rows = naive_visible(&structure);
+        prop_assert_eq!(Some(structure.last_visible()), rows.last().copied());
+        prop_assert_eq!(structure.next_visible(structure.last_visible()), None);
+    }
+
+    #[test]
+    fn nearest_visible_is_row_above_node(case in case()) {
+        let structure = structure(&case);
+        let rows = naive_visible(&structure);
+        for index in (0..structure.len()).map(JsonMetadataIndex::new) {
+            let nearest = structure.nearest_visible(index);
+            prop_assert!(rows.contains(&nearest));
+            let is_ancestor = std::iter::successors(Some(index), |&index| structure.parent(index))
+                .any(|ancestor| ancestor == nearest);
+            prop_assert!(is_ancestor);
+            if rows.contains(&index) {
+                prop_assert_eq!(nearest, index);
+            }
+        }
+    }
+
+    #[test]
+    fn edits_update_nodes_like_parsing_again(
+        case in case(),
+        edits in prop::collection::vec(edit(), 1..8),
+    ) {
+        let mut content = Document::from(case.content.clone().into_bytes());
+        let mut structure = parse_json_structure_lazy(case.content.as_bytes()).unwrap();
+        // edits of partially indexed document
+        for (index, &expanded) in case.expanded.iter().enumerate() {
+            if index < structure.len() {
+                structure
+            .set_expanded(&content, JsonMetadataIndex::new(index), expanded)
+            .unwrap();
+            }
+        }
+        let mut patch = Patch::default();
+        for edit in edits {
+            let attached = attached(&structure);
+            let pick = |node: usize| attached[node % attached.len()];
+            // top level value, containers and object members can't have some edits
+            let _ = match edit {
+                Edit::Replace(node, value) => {
+                    patch.replace_value(&mut content, &mut structure, pick(node), value)
+                }
+                Edit::Delete(node) => patch.delete(&mut content, &mut structure, pick(node)),
+                Edit::Duplicate(node) => patch.duplicate(&mut content, &mut structure, pick(node)),
+                Edit::Undo => Ok(_ = patch.undo(&mut content, &mut structure)),
+            };
+        }
+        check_like_parsed(&content, &mut structure)?;
+        while patch.undo(&mut content, &mut structure) {}
+        prop_assert_eq!(content.to_vec(), case.content.as_bytes());
+        check_like_parsed(&content, &mut structure)?;
+    }
+}
+
+#[test]
+fn invalid_json_keeps_container_collapsed() {
+    let text = r#"[{"a": 1, "b": @}, {"c" 2}, [3]]"#;
+    let content = Document::from(text.as_bytes());
+    let mut structure = parse_json_structure_lazy(text.as_bytes()).unwrap();
+    let children: Vec<_> = structure.children(JsonMetadataIndex::ROOT).collect();
+    for (&child, offset) in children.iter().zip([15, 24]) {
+        let err = structure.set_expanded(&content, child, true).unwrap_err();
+        assert_eq!(err.to_string(), format!("invalid JSON at byte {offset}"));
+        assert!(!structure.is_expanded(child));
+        assert_eq!(structure.first_child(child), None);
+    }
+    // nodes of invalid containers are dropped, the valid one is indexed after them
+    structure.set_expanded(&content, children[2], true).unwrap();
+    assert_eq!(structure.len(), 5);
+    assert!(structure.index_all(&content).is_err());
+}
+
+#[test]
+fn truncated_document_is_invalid() {
+    for (text, offset) in [("[1, [2, 3", 9), (r#"{"a": "b"#, 8), (r#"{"a": tru}"#, 6)] {
+        let Err(err) = parse_json_structure_lazy(text.as_bytes()) else {
+            panic!("{text} is parsed");
+        };
+        assert_eq!(err.to_string(), format!("invalid JSON at byte {offset}"));
+    }
+}
+
+#[test]
+fn stream_documents_are_elements_of_root() {
+    let text = "{\"a\": 1}\n[2]\n3\n";
+    let content = Document::from(text.as_bytes());
+    let structure = parse_json_structure_lazy(text.as_bytes()).unwrap();
+    let root = structure.get(JsonMetadataIndex::ROOT);
+    assert!(matches!(root.name_or_index, NameOrIndex::Root));
+    let documents: Vec<_> = structure
+        .children(JsonMetadataIndex::ROOT)
+        .map(|index| path_to(&content, &structure, index))
+        .collect();
+    assert_eq!(documents, [".[0]", ".[1]", ".[2]"]);
+    let single = parse_json_structure_lazy(b"[1]").unwrap();
+    let root = single.get(JsonMetadataIndex::ROOT);
+    assert!(matches!(root.name_or_index, NameOrIndex::Root));
+}
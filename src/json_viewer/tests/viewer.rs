@@ -0,0 +1,880 @@
+//! Replays keys against `Viewer` and `DiffViewer` and checks frames drawn to a virtual terminal
+
+use json_viewer::*;
+
+const DOCUMENT: &str = r#"{
+  "name": "viewer",
+  "tags": ["json", "tui"],
+  "owner": {"login": "octo", "admin": true},
+  "count": 3
+}"#;
+
+/// Minimal terminal, that understands sequences written by `Screen` and `Viewer`
+struct VirtualTerminal {
+    cells: Vec<Vec<char>>,
+    /// Cells drawn with reverse video, monochrome theme uses it for selection
+    reverse: Vec<Vec<bool>>,
+    row: usize,
+    column: usize,
+    reverse_video: bool,
+    /// Decoded payloads of OSC 52
+    clipboard: Vec<String>,
+}
+
+impl VirtualTerminal {
+    fn new(width: u16, height: u16) -> Self {
+        VirtualTerminal {
+            cells: vec![vec![' '; width as usize]; height as usize],
+            reverse: vec![vec![false; width as usize]; height as usize],
+            row: 0,
+            column: 0,
+            reverse_video: false,
+            clipboard: Vec::new(),
+        }
+    }
+
+    fn write(&mut self, output: &[u8]) {
+        let text = str::from_utf8(output).unwrap();
+        let mut chars = text.chars();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '\x1b' => match chars.next() {
+                    Some('[') => {
+                        let mut params = String::new();
+                        let mut final_char = None;
+                        for ch in chars.by_ref() {
+                            if ch.is_ascii_alphabetic() {
+                                final_char = Some(ch);
+                                break;
+                            }
+                            params.push(ch);
+                        }
+                        self.csi(&params, final_char.unwrap());
+                    }
+                    Some(']') => {
+                        let mut payload = String::new();
+                        for ch in chars.by_ref() {
+                            if ch == '\x07' {
+                                break;
+                            }
+                            payload.push(ch);
+                        }
+                        if let Some(data) = payload.strip_prefix("52;c;") {
+                            self.clipboard.push(data.to_owned());
+                        }
+                    }
+                    other => panic!("unexpected escape {other:?}"),
+                },
+                ch => {
+                    let width = self.cells[0].len();
+                    if self.column < width {
+                        self.cells[self.row][self.column] = ch;
+                        self.reverse[self.row][self.column] = self.reverse_video;
+                    }
+                    self.column += 1;
+                }
+            }
+        }
+    }
+
+    fn csi(&mut self, params: &str, final_char: char) {
+        match final_char {
+            'H' => {
+                let (row, column) = params.split_once(';').unwrap();
+                self.row = row.parse::<usize>().unwrap() - 1;
+                self.column = column.parse::<usize>().unwrap() - 1;
+            }
+            'J' if params == "2" => {
+                for (cells, reverse) in self.cells.iter_mut().zip(&mut self.reverse) {
+                    cells.fill(' ');
+                    reverse.fill(false);
+                }
+            }
+            'K' if params == "2" => {
+                self.cells[self.row].fill(' ');
+                self.reverse[self.row].fill(false);
+            }
+            'm' => {
+                for param in params.split(';') {
+                    match param {
+                        "0" | "" => self.reverse_video = false,
+                        "7" => self.reverse_video = true,
+                        _ => {}
+                    }
+                }
+            }
+            // synchronized update
+            'h' | 'l' if params == "?2026" => {}
+            _ => panic!("unexpected sequence {params:?} {final_char}"),
+        }
+    }
+
+    fn line(&self, row: usize) -> String {
+        self.cells[row]
+            .iter()
+            .collect::<String>()
+            .trim_end()
+            .to_owned()
+    }
+    fn lines(&self) -> Vec<String> {
+        (0..self.cells.len()).map(|row| self.line(row)).collect()
+    }
+    fn status_line(&self) -> String {
+        self.line(self.cells.len() - 1)
+    }
+    /// Text of reversed cells in each row, where they are
+    fn selected(&self) -> Vec<String> {
+        (0..self.cells.len())
+            .map(|row| {
+                self.cells[row]
+                    .iter()
+                    .zip(&self.reverse[row])
+                    .filter(|&(_, &reverse)| reverse)
+                    .map(|(&ch, _)| ch)
+                    .collect::<String>()
+                    .trim_end()
+                    .to_owned()
+            })
+            .filter(|text| !text.is_empty())
+            .collect()
+    }
+}
+
+struct Harness<V = Viewer> {
+    viewer: V,
+    screen: Screen,
+    terminal: VirtualTerminal,
+}
+
+impl Harness {
+    fn new(document: &'static str, width: u16, height: u16) -> Self {
+        let viewer = Viewer::new(
+            Document::from(document.as_bytes()),
+            parse_json_structure_lazy(document.as_bytes()).unwrap(),
+            width,
+            height,
+            ViewerOptions {
+                path: None,
+                yank_file: None,
+                lenient: false,
+                theme: Theme::new(ThemeName::Monochrome, ColorDepth::Ansi16),
+            },
+        );
+        Harness::with(viewer, width, height)
+    }
+}
+
+impl Harness<DiffViewer> {
+    fn diff(left: &'static str, right: &'static str, width: u16, height: u16) -> Self {
+        let side = |document: &'static str| Side {
+            content: Document::from(document.as_bytes()),
+            structure: parse_json_structure(document.as_bytes()).unwrap(),
+        };
+        let (left, right) = (side(left), side(right));
+        let diff = diff_structures(
+            &left.content,
+            &left.structure,
+            &right.content,
+            &right.structure,
+        );
+        let theme = Theme::new(ThemeName::Monochrome, ColorDepth::Ansi16);
+        let viewer = DiffViewer::new(left, right, diff, width, height, theme);
+        Harness::with(viewer, width, height)
+    }
+}
+
+impl<V: Interactive> Harness<V> {
+    fn with(viewer: V, width: u16, height: u16) -> Self {
+        let mut harness = Harness {
+            viewer,
+            screen: Screen::new(width, height),
+            terminal: VirtualTerminal::new(width, height),
+        };
+        harness.draw();
+        harness
+    }
+
+    fn draw(&mut self) {
+        let mut output = Vec::new();
+        self.screen.draw(&mut output, self.viewer.render()).unwrap();
+        self.terminal.write(&output);
+    }
+
+    /// Keys are raw terminal input, like `"j\x1b[B"`
+    /// # Returns exit request of the last key
+    fn replay(&mut self, input: &str) -> Option<Exit> {
+        let mut exit = None;
+        for key in decode_keys(input.as_bytes()) {
+            exit = self.viewer.handle_key(key);
+            let output = self.viewer.take_terminal_output();
+            self.terminal.write(&output);
+            self.draw();
+        }
+        exit
+    }
+
+    fn overview(&self) -> Vec<String> {
+        let lines = self.terminal.lines();
+        lines[..lines.len() - 1]
+            .iter()
+            .map(|line| line.split(" |").next().unwrap().trim_end().to_owned())
+            .filter(|line| !line.is_empty())
+            .collect()
+    }
+}
+
+#[test]
+fn first_frame_shows_top_level_members() {
+    let harness = Harness::new(DOCUMENT, 60, 8);
+    assert_eq!(
+        harness.terminal.lines(),
+        [
+            "0 obj                    | {",
+            "  name \"viewer\"          |   \"name\": \"viewer\",",
+            "+ tags arr               |   \"tags\": […],",
+            "+ owner obj              |   \"owner\": {…},",
+            "  count 3                |   \"count\": 3",
+            "                         | }",
+            "                         |",
+            ".",
+        ]
+    );
+    assert_eq!(harness.terminal.selected(), ["0 obj"]);
+}
+
+#[test]
+fn navigation_moves_selection() {
+    let mut harness = Harness::new(DOCUMENT, 60, 8);
+    harness.replay("jj");
+    assert_eq!(harness.terminal.selected(), ["+ tags arr"]);
+    assert_eq!(harness.terminal.status_line(), ".tags");
+    assert_eq!(harness.terminal.line(0), "0 obj                    | [");
+    harness.replay("\r");
+    assert_eq!(
+        harness.overview(),
+        [
+            "0 obj",
+            "  name \"viewer\"",
+            "- tags arr",
+            "    0 \"json\"",
+            "    1 \"tui\"",
+            "+ owner obj",
+            "  count 3",
+        ]
+    );
+    harness.replay("G");
+    assert_eq!(harness.terminal.selected(), ["  count 3"]);
+    assert_eq!(harness.terminal.status_line(), ".count");
+    harness.replay("kk\x1b[D");
+    assert_eq!(harness.terminal.selected(), ["- tags arr"]);
+    harness.replay("hg");
+    assert_eq!(harness.terminal.selected(), ["0 obj"]);
+}
+
+#[test]
+fn data_pane_follows_focus() {
+    let mut harness = Harness::new(DOCUMENT, 60, 8);
+    harness.replay("\tjj\r");
+    assert_eq!(harness.terminal.selected(), ["0 obj", "  \"tags\": ["]);
+    assert_eq!(
+        harness.terminal.line(3),
+        "    0 \"json\"             |     \"json\","
+    );
+    // selection of overview stays on root, it's root of data pane
+    assert_eq!(harness.terminal.status_line(), ".");
+}
+
+#[test]
+fn search_jumps_to_matches() {
+    let mut harness = Harness::new(DOCUMENT, 60, 8);
+    harness.replay("/tu");
+    assert_eq!(harness.terminal.status_line(), "/tu");
+    harness.replay("\r");
+    assert_eq!(harness.terminal.selected(), ["    1 \"tui\""]);
+    assert_eq!(harness.terminal.status_line(), ".tags[1]    /tu [1/1]");
+    harness.replay(":.owner.login\r");
+    assert_eq!(harness.terminal.selected(), ["    login \"octo\""]);
+    harness.replay(":owner\r");
+    assert_eq!(
+        harness.terminal.status_line(),
+        ".owner.login    :owner: expected '.' or '[' at 'owner'"
+    );
+}
+
+#[test]
+fn filter_hides_other_nodes() {
+    let mut harness = Harness::new(DOCUMENT, 60, 8);
+    harness.replay("&octo");
+    assert_eq!(
+        harness.overview(),
+        ["0 obj", "- owner obj", "    login \"octo\""]
+    );
+    harness.replay("\r");
+    assert_eq!(
+        harness.terminal.status_line(),
+        "[filtered] .    &octo [?/1]"
+    );
+    assert!(harness.viewer.session().expanded.is_empty());
+    // typing narrows matches, erasing searches again
+    harness.replay("&octoz");
+    assert_eq!(harness.overview(), ["0 obj"]);
+    harness.replay("\x7f");
+    assert_eq!(
+        harness.overview(),
+        ["0 obj", "- owner obj", "    login \"octo\""]
+    );
+    harness.replay("\x1b");
+    assert_eq!(
+        harness.overview(),
+        [
+            "0 obj",
+            "  name \"viewer\"",
+            "+ tags arr",
+            "+ owner obj",
+            "  count 3"
+        ]
+    );
+}
+
+#[test]
+fn edits_change_content_and_warn_on_quit() {
+    let mut harness = Harness::new(DOCUMENT, 60, 8);
+    harness.replay(":.owner.admin\rt");
+    assert_eq!(harness.terminal.status_line(), "[modified] .owner.admin");
+    assert!(
+        str::from_utf8(&harness.viewer.content().to_vec())
+            .unwrap()
+            .contains(r#""admin": false"#)
+    );
+    assert_eq!(harness.replay("q"), None);
+    assert_eq!(
+        harness.terminal.status_line(),
+        "unsaved edits, press again to quit"
+    );
+    harness.replay("U");
+    assert_eq!(harness.viewer.content().to_vec(), DOCUMENT.as_bytes());
+    assert_eq!(harness.replay("q"), Some(Exit::Quit));
+}
+
+#[test]
+fn deletes_and_copies_keep_expansion_until_undone() {
+    let mut harness = Harness::new(DOCUMENT, 60, 10);
+    harness.replay("jj\rjD");
+    assert_eq!(
+        harness.overview()[2..],
+        [
+            "- tags arr",
+            "    0 \"json\"",
+            "    1 \"json\"",
+            "    2 \"tui\"",
+            "+ owner obj",
+            "  count 3",
+        ]
+    );
+    assert!(
+        str::from_utf8(&harness.viewer.content().to_vec())
+            .unwrap()
+            .contains(r#""tags": ["json", "json", "tui"],"#)
+    );
+    harness.replay("jjdd");
+    assert_eq!(harness.terminal.selected(), ["    1 \"json\""]);
+    harness.replay("kdd");
+    assert_eq!(harness.terminal.selected(), ["- tags arr"]);
+    assert_eq!(
+        harness.overview()[2..],
+        ["- tags arr", "    0 \"json\"", "+ owner obj", "  count 3"]
+    );
+    harness.replay("UUU");
+    assert_eq!(harness.viewer.content().to_vec(), DOCUMENT.as_bytes());
+    assert_eq!(
+        harness.overview()[2..],
+        [
+            "- tags arr",
+            "    0 \"json\"",
+            "    1 \"tui\"",
+            "+ owner obj",
+            "  count 3",
+        ]
+    );
+}
+
+#[test]
+fn schema_is_swapped_with_document() {
+    let mut harness = Harness::new(DOCUMENT, 60, 8);
+    harness.replay("jjS");
+    assert_eq!(harness.terminal.status_line(), "[schema] .");
+    assert_eq!(
+        harness.overview()[2..4],
+        ["  type \"object\"", "  x-count 1"]
+    );
+    harness.replay("jS");
+    assert_eq!(harness.terminal.selected(), ["+ tags arr"]);
+    // schema is kept with its selection
+    harness.replay("S");
+    assert_eq!(harness.terminal.selected(), ["  $schema \"https://json…"]);
+    harness.replay("t");
+    assert_eq!(harness.terminal.status_line(), "schema can't be edited");
+}
+
+#[test]
+fn numbers_with_exponent_are_parsed() {
+    let mut harness = Harness::new("[1e5, {\"a\": -2.5E-3}]", 60, 5);
+    harness.replay("jzR");
+    assert_eq!(
+        harness.overview(),
+        ["0 arr", "  0 1e5", "- 1 obj", "    a -2.5E-3"]
+    );
+    harness.replay(&format!("e{}3E+2\r", "\x7f".repeat(3)));
+    assert_eq!(
+        harness.viewer.content().to_vec(),
+        b"[3E+2, {\"a\": -2.5E-3}]"
+    );
+    assert_eq!(harness.terminal.selected(), ["  0 3E+2"]);
+}
+
+#[test]
+fn yank_copies_through_terminal() {
+    let mut harness = Harness::new(DOCUMENT, 60, 8);
+    harness.replay(":.count\ry");
+    assert_eq!(harness.terminal.clipboard, ["Mw=="]);
+    assert_eq!(
+        harness.terminal.status_line(),
+        "copied 1 bytes to clipboard"
+    );
+    assert_eq!(harness.replay("Q"), Some(Exit::QuitWithoutPrinting));
+}
+
+#[test]
+fn resize_redraws_frame() {
+    let mut harness = Harness::new(DOCUMENT, 60, 8);
+    harness.viewer.resize(30, 4);
+    harness.screen.resize(30, 4);
+    harness.terminal = VirtualTerminal::new(30, 4);
+    harness.replay("G");
+    assert_eq!(
+        harness.overview(),
+        ["+ tags arr", "+ owner obj", "  count 3"]
+    );
+    assert_eq!(harness.terminal.status_line(), ".count");
+}
+
+const NUMBERS: &str = "[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19]";
+
+/// SGR report of left button or wheel, `column` and `row` start from 0
+fn mouse(button: u8, column: u16, row: u16, release: bool) -> String {
+    let end = if release { 'm' } else { 'M' };
+    format!("\x1b[<{button};{};{}{end}", column + 1, row + 1)
+}
+
+#[test]
+fn mouse_reports_are_decoded() {
+    let input = format!("{}{}", mouse(0, 4, 2, false), mouse(65, 0, 0, false));
+    assert_eq!(
+        decode_keys(input.as_bytes()),
+        [
+            Key::Mouse(Mouse {
+                kind: MouseKind::Press,
+                column: 4,
+                row: 2,
+            }),
+            Key::Mouse(Mouse {
+                kind: MouseKind::WheelDown,
+                column: 0,
+                row: 0,
+            }),
+        ]
+    );
+    // right button
+    assert_eq!(
+        decode_keys(mouse(2, 0, 0, false).as_bytes()),
+        [Key::Unknown]
+    );
+}
+
+#[test]
+fn click_selects_node_and_toggles_marker() {
+    let mut harness = Harness::new(DOCUMENT, 60, 8);
+    harness.replay(&mouse(0, 5, 3, false));
+    assert_eq!(harness.terminal.status_line(), ".owner");
+    harness.replay(&mouse(0, 0, 2, false));
+    assert_eq!(
+        harness.overview()[..5],
+        [
+            "0 obj",
+            "  name \"viewer\"",
+            "- tags arr",
+            "    0 \"json\"",
+            "    1 \"tui\""
+        ]
+    );
+    assert_eq!(harness.terminal.selected(), ["+ owner obj"]);
+    // click below document
+    harness.replay(&mouse(0, 5, 7, false));
+    assert_eq!(harness.terminal.status_line(), ".owner");
+}
+
+#[test]
+fn click_in_data_pane_moves_cursor() {
+    let mut harness = Harness::new(DOCUMENT, 60, 8);
+    harness.replay(&mouse(0, 30, 2, false));
+    assert_eq!(harness.terminal.selected(), ["0 obj", "  \"tags\": […],"]);
+    harness.replay("\r");
+    assert_eq!(
+        harness.terminal.line(3),
+        "    0 \"json\"             |     \"json\","
+    );
+}
+
+#[test]
+fn wheel_scrolls_overview_and_keeps_selection_on_screen() {
+    let mut harness = Harness::new(NUMBERS, 60, 8);
+    harness.replay(&mouse(65, 0, 0, false));
+    assert_eq!(harness.overview()[0], "  2 2");
+    assert_eq!(harness.terminal.status_line(), ".[2]");
+    harness.replay(&mouse(64, 0, 0, false));
+    assert_eq!(harness.overview()[0], "0 arr");
+    assert_eq!(harness.terminal.status_line(), ".[2]");
+    for _ in 0..10 {
+        harness.replay(&mouse(65, 0, 0, false));
+    }
+    // last element stays at the bottom
+    assert_eq!(harness.overview().first().unwrap(), "  13 13");
+    assert_eq!(harness.overview().last().unwrap(), "  19 19");
+}
+
+#[test]
+fn wheel_scrolls_data_pane() {
+    let mut harness = Harness::new(NUMBERS, 60, 8);
+    harness.replay(&mouse(65, 30, 0, false));
+    assert_eq!(harness.terminal.line(0), "0 arr                    |   2,");
+    harness.replay(&mouse(64, 30, 0, false));
+    assert_eq!(harness.terminal.line(0), "0 arr                    | [");
+}
+
+#[test]
+fn dragging_divider_resizes_panes() {
+    let mut harness = Harness::new(DOCUMENT, 60, 8);
+    harness.replay(&mouse(0, 25, 0, false));
+    harness.replay(&mouse(32, 10, 0, false));
+    harness.replay(&mouse(0, 10, 0, true));
+    assert_eq!(harness.terminal.line(0), "0 obj      | {");
+    // released, so motion doesn't resize
+    harness.replay(&mouse(32, 20, 0, false));
+    assert_eq!(harness.terminal.line(0), "0 obj      | {");
+}
+
+const EVENT: &str = r#"{"at": 1700000000, "payload": "{\"id\": 7, \"tags\": [\"a\"]}"}"#;
+
+#[test]
+fn data_pane_shows_hints() {
+    let mut harness = Harness::new(EVENT, 80, 6);
+    assert_eq!(
+        harness.terminal.line(1),
+        "  at 1700000000                  |   \"at\": 1700000000,  // 2023-11-14T22:13:20Z"
+    );
+    harness.replay("i");
+    assert_eq!(
+        harness.terminal.line(1),
+        "  at 1700000000                  |   \"at\": 1700000000,"
+    );
+}
+
+#[test]
+fn embedded_json_is_decoded_and_encoded_back() {
+    let mut harness = Harness::new(EVENT, 80, 6);
+    harness.replay("Gx");
+    assert_eq!(harness.terminal.status_line(), "[decoded] .payload");
+    // decoded json is browsed, but document isn't changed
+    assert_eq!(harness.viewer.content().to_vec(), EVENT.as_bytes());
+    harness.replay("\r");
+    assert_eq!(
+        harness.overview(),
+        [
+            "0 obj",
+            "  at 1700000000",
+            "- payload obj",
+            "    id 7",
+            "  + tags arr"
+        ]
+    );
+    harness.replay("t");
+    assert_eq!(
+        harness.terminal.status_line(),
+        "decoded strings should be encoded back with 'x' before edits"
+    );
+    harness.replay("x");
+    assert_eq!(harness.viewer.content().to_vec(), EVENT.as_bytes());
+    assert_eq!(harness.terminal.status_line(), ".payload");
+}
+
+#[test]
+fn nested_embedded_json_is_encoded_back_with_outer_string() {
+    let document = r#"{"outer": "{\"inner\": \"[1, 2]\"}"}"#;
+    let mut harness = Harness::new(document, 60, 6);
+    harness.replay("Gx\rjx\r");
+    assert_eq!(
+        harness.overview(),
+        [
+            "0 obj",
+            "- outer obj",
+            "  - inner arr",
+            "      0 1",
+            "      1 2"
+        ]
+    );
+    assert_eq!(harness.terminal.status_line(), "[decoded] .outer.inner");
+    harness.replay("kx");
+    assert_eq!(harness.overview()[1], "  outer \"{\\\"inner\\\": \\\"…");
+    assert_eq!(harness.terminal.status_line(), ".outer");
+    assert_eq!(harness.viewer.content().to_vec(), document.as_bytes());
+}
+
+const USERS: &str = r#"[{"name": "bob", "age": 31}, {"name": "alice", "age": 25}, {"name": "carol"}, {"name": "dave", "age": 40}]"#;
+
+#[test]
+fn sorting_changes_only_overview_order() {
+    let mut harness = Harness::new(USERS, 60, 8);
+    harness.replay("o-.age\r");
+    assert_eq!(
+        harness.overview(),
+        ["0 arr", "+ 3 obj", "+ 0 obj", "+ 1 obj", "+ 2 obj"]
+    );
+    assert_eq!(harness.terminal.status_line(), "[sorted] .");
+    harness.replay("o.name\rj");
+    assert_eq!(harness.terminal.selected(), ["+ 1 obj"]);
+    harness.replay("jO");
+    assert_eq!(harness.terminal.selected(), ["+ 0 obj"]);
+    assert_eq!(
+        harness.overview(),
+        ["0 arr", "+ 0 obj", "+ 1 obj", "+ 2 obj", "+ 3 obj"]
+    );
+    assert_eq!(harness.viewer.content().to_vec(), USERS.as_bytes());
+    harness.replay("O");
+    assert_eq!(harness.terminal.status_line(), "already in document order");
+}
+
+#[test]
+fn failed_sort_keeps_container_collapsed() {
+    let mut harness = Harness::new(USERS, 60, 8);
+    harness.replay("jo.[\r");
+    assert_eq!(harness.terminal.selected(), ["+ 0 obj"]);
+    assert_eq!(harness.overview().len(), 5);
+}
+
+#[test]
+fn table_shows_array_of_objects() {
+    let mut harness = Harness::new(USERS, 60, 6);
+    harness.replay("T");
+    assert_eq!(
+        harness.terminal.lines(),
+        [
+            "0 arr                    | #  name     age",
+            "+ 0 obj                  | 0  \"bob\"    31",
+            "+ 1 obj                  | 1  \"alice\"  25",
+            "+ 2 obj                  | 2  \"carol\"",
+            "+ 3 obj                  | 3  \"dave\"   40",
+            "[table] .",
+        ]
+    );
+    harness.replay("jl");
+    assert_eq!(harness.terminal.selected(), ["0 arr", "25"]);
+    harness.replay("-");
+    assert_eq!(
+        harness.terminal.line(0),
+        "0 arr                    | #  name"
+    );
+    harness.replay("+");
+    assert_eq!(harness.terminal.status_line(), "shown 1 hidden columns");
+    harness.replay("\r");
+    assert_eq!(harness.terminal.status_line(), ".[1].name");
+    assert!(harness.terminal.line(0).ends_with("| \"alice\""));
+    harness.replay(":.[0].age\rT");
+    assert_eq!(
+        harness.terminal.status_line(),
+        "only arrays of objects could be shown as table"
+    );
+}
+
+#[test]
+fn click_in_table_moves_cursor_and_filter_hides_rows() {
+    let mut harness = Harness::new(USERS, 60, 6);
+    harness.replay("T");
+    // age of alice
+    harness.replay(&mouse(0, 41, 2, false));
+    assert_eq!(harness.terminal.selected(), ["0 arr", "25"]);
+    // header keeps cursor
+    harness.replay(&mouse(0, 32, 0, false));
+    assert_eq!(harness.terminal.selected(), ["0 arr", "25"]);
+    harness.replay(&mouse(0, 32, 4, false));
+    assert_eq!(harness.terminal.selected(), ["0 arr", "\"dave\""]);
+    harness.replay("&dave\r");
+    assert_eq!(
+        harness.terminal.lines(),
+        [
+            "0 arr                    | #  name     age",
+            "- 3 obj                  | 3  \"dave\"   40",
+            "    name \"dave\"          |",
+            "                         |",
+            "                         |",
+            "[filtered] [table] .    &dave [?/1]",
+        ]
+    );
+}
+
+#[test]
+fn table_scrolls_to_cursor_and_survives_edits() {
+    let mut harness = Harness::new(USERS, 40, 4);
+    harness.replay("o-.age\rT");
+    assert_eq!(
+        harness.terminal.lines(),
+        [
+            "0 arr            | #  name    age",
+            "+ 3 obj          | 3  \"dave\"  40",
+            "+ 0 obj          | 0  \"bob\"   31",
+            "[sorted] [table] .",
+        ]
+    );
+    // widths and columns grow with rows scrolled into view
+    harness.replay("Gl");
+    assert_eq!(harness.terminal.line(2), "+ 0 obj          | 2  \"carol\"");
+    harness.replay("jkh");
+    assert_eq!(harness.terminal.selected(), ["0 arr", "\"alice\""]);
+    harness.replay(&format!("e{}\"eve\"\r", "\x7f".repeat(7)));
+    // sort key could be edited, so it's in document order
+    assert_eq!(harness.terminal.status_line(), "[modified] [table] .");
+    assert_eq!(harness.terminal.selected(), ["0 arr", "\"eve\""]);
+}
+
+fn diff_statuses(left: &str, right: &str) -> Vec<DiffStatus> {
+    let left_content = Document::from(left.as_bytes().to_vec());
+    let right_content = Document::from(right.as_bytes().to_vec());
+    let diff = diff_structures(
+        &left_content,
+        &parse_json_structure(left.as_bytes()).unwrap(),
+        &right_content,
+        &parse_json_structure(right.as_bytes()).unwrap(),
+    );
+    (0..diff.len())
+        .map(|index| diff.get(DiffIndex::new(index)).status)
+        .collect()
+}
+
+#[test]
+fn diff_pairs_duplicate_keys_in_order() {
+    use DiffStatus::*;
+    assert_eq!(
+        diff_statuses(r#"{"a": 1}"#, r#"{"a": 1, "a": 2}"#),
+        [Modified, Same, Added]
+    );
+    assert_eq!(
+        diff_statuses(r#"{"a": 1, "a": 2, "b": 3}"#, r#"{"a": 1, "a": 3}"#),
+        [Modified, Same, Changed, Removed]
+    );
+}
+
+#[test]
+fn diff_of_deeply_nested_documents() {
+    let depth = 100_000;
+    let left = format!("{}1{}", "[".repeat(depth), "]".repeat(depth));
+    let right = format!("{}2{}", "[".repeat(depth), "]".repeat(depth));
+    let statuses = diff_statuses(&left, &right);
+    assert_eq!(statuses.len(), depth + 1);
+    assert_eq!(statuses[0], DiffStatus::Modified);
+    assert_eq!(statuses[depth], DiffStatus::Changed);
+}
+
+const LEFT: &str = r#"{"name": "viewer", "tags": ["json", "tui"], "count": 3}"#;
+const RIGHT: &str = r#"{"name": "viewer", "tags": ["json", "cli"], "count": 4, "new": true}"#;
+
+#[test]
+fn diff_jumps_between_differences() {
+    let mut harness = Harness::diff(LEFT, RIGHT, 60, 6);
+    assert_eq!(
+        harness.terminal.line(4),
+        "    1 \"tui\"                  |     1 \"cli\""
+    );
+    assert_eq!(harness.terminal.status_line(), ".    [difference ?/3]");
+    harness.replay("n");
+    assert_eq!(
+        harness.terminal.selected(),
+        ["    1 \"tui\"                     1 \"cli\""]
+    );
+    assert_eq!(
+        harness.terminal.status_line(),
+        ".tags[1]    [difference 1/3]"
+    );
+    // wraps around to last difference, that is only on the right
+    harness.replay("N");
+    assert_eq!(
+        harness.terminal.selected(),
+        [format!("{}new true", " ".repeat(30))]
+    );
+    assert_eq!(harness.terminal.status_line(), ".new    [difference 3/3]");
+    assert_eq!(harness.replay("q"), Some(Exit::Quit));
+}
+
+#[test]
+fn diff_moves_selection_and_collapses() {
+    let mut harness = Harness::diff(LEFT, RIGHT, 60, 4);
+    harness.replay("jj\r");
+    assert_eq!(
+        harness.overview(),
+        ["0 obj", "  name \"viewer\"", "+ tags arr"]
+    );
+    harness.replay("G");
+    assert_eq!(
+        harness.terminal.lines()[..3],
+        [
+            "+ tags arr                   | + tags arr",
+            "  count 3                    |   count 4",
+            "                             |   new true",
+        ]
+    );
+    harness.replay("gl");
+    assert_eq!(harness.terminal.status_line(), ".name    [difference ?/3]");
+}
+
+#[test]
+fn diff_click_selects_node_and_toggles_marker() {
+    let mut harness = Harness::diff(LEFT, RIGHT, 60, 6);
+    // marker of right half
+    harness.replay(&mouse(0, 31, 2, false));
+    assert_eq!(
+        harness.overview(),
+        ["0 obj", "  name \"viewer\"", "+ tags arr", "  count 3"]
+    );
+    harness.replay(&mouse(0, 10, 3, false));
+    assert_eq!(harness.terminal.status_line(), ".count    [difference 2/3]");
+    // status line
+    harness.replay(&mouse(0, 10, 5, false));
+    assert_eq!(harness.terminal.status_line(), ".count    [difference 2/3]");
+}
+
+#[test]
+fn diff_wheel_scrolls_and_keeps_selection_on_screen() {
+    let mut harness = Harness::diff(LEFT, RIGHT, 60, 4);
+    harness.replay(&mouse(65, 0, 0, false));
+    assert_eq!(
+        harness.overview(),
+        ["    0 \"json\"", "    1 \"tui\"", "  count 3"]
+    );
+    assert_eq!(
+        harness.terminal.status_line(),
+        ".tags[0]    [difference ?/3]"
+    );
+    harness.replay(&mouse(64, 40, 0, false));
+    assert_eq!(harness.overview()[0], "0 obj");
+    assert_eq!(harness.terminal.status_line(), ".tags    [difference ?/3]");
+}
+
+#[test]
+fn invalid_json_is_reported_in_status_line() {
+    let mut harness = Harness::new(r#"[{"a": 1, "b": @}, 2]"#, 40, 5);
+    harness.replay("j");
+    assert_eq!(harness.terminal.status_line(), "invalid JSON at byte 15");
+    harness.replay("l");
+    assert_eq!(harness.terminal.status_line(), "invalid JSON at byte 15");
+    assert_eq!(harness.overview(), ["0 arr", "+ 0 obj", "  1 2"]);
+    harness.replay("j");
+    assert_eq!(harness.terminal.status_line(), ".[1]");
+}